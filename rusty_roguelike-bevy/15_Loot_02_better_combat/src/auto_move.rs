@@ -0,0 +1,66 @@
+use crate::prelude::*;
+use std::collections::HashSet;
+
+// Present while the player moves automatically, one step per turn; it's removed when the destination is
+// reached, on any keypress, or when a monster comes into view.
+//
+pub struct AutoMove {
+    // None when exploring.
+    pub destination: Option<Point>,
+    // The enemies already in sight when the movement started; they don't interrupt it.
+    pub known_enemies: HashSet<Entity>,
+}
+
+// A view of the map restricted to the revealed tiles, so that the automatic movement doesn't leak the
+// layout of the unexplored areas.
+//
+pub struct RevealedMap<'a>(pub &'a Map);
+
+impl Algorithm2D for RevealedMap<'_> {
+    fn dimensions(&self) -> Point {
+        self.0.dimensions()
+    }
+
+    fn in_bounds(&self, point: Point) -> bool {
+        self.0.in_bounds(point)
+    }
+}
+
+impl BaseMap for RevealedMap<'_> {
+    fn is_opaque(&self, idx: usize) -> bool {
+        self.0.is_opaque(idx)
+    }
+
+    fn get_available_exits(&self, idx: usize) -> SmallVec<[(usize, f32); 10]> {
+        self.0
+            .get_available_exits(idx)
+            .into_iter()
+            .filter(|(exit_idx, _)| self.0.revealed_tiles[*exit_idx])
+            .collect()
+    }
+
+    fn get_pathing_distance(&self, idx1: usize, idx2: usize) -> f32 {
+        self.0.get_pathing_distance(idx1, idx2)
+    }
+}
+
+impl RevealedMap<'_> {
+    // Revealed tiles that can be entered, and are next to unrevealed ones. Closed doors are included, since
+    // walking into them opens them, revealing what's behind; locked ones are not, since the player may not
+    // have a key.
+    //
+    pub fn frontier(&self) -> Vec<usize> {
+        (0..self.0.tiles.len())
+            .filter(|idx| {
+                let pos = self.0.index_to_point2d(*idx);
+                self.0.revealed_tiles[*idx]
+                    && (self.0.can_enter_tile(pos) || self.0.tiles[*idx] == TileType::Door)
+                    && DIRECTIONS.iter().any(|delta| {
+                        self.0
+                            .try_idx(pos + *delta)
+                            .is_some_and(|idx| !self.0.revealed_tiles[idx])
+                    })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,274 @@
+// Headless harness for the map builders (the Bevy port equivalent of the source project's
+// `11_MoreInterestingDungeons_04_output_harness` step), in order to evaluate the generators without
+// opening a window (e.g. in CI).
+//
+// Usage: map_harness [--architect <empty|rooms|automata|drunkard|bsp|maze|voronoi|all>]
+//                    [--meta <doors|smooth|cull|traps|cracks>[,...]] [--seed <n>] [--count <n>] [--level <n>]
+//                    [--stats-only] [--validate]
+//
+// The meta builders are applied, in the given order, to the maps of all the selected architects.
+//
+// The maps are printed as ASCII, followed by the statistics; `@` is the player start, `A` the amulet
+// start, `M` a monster spawn, `I` an item spawn, `K` a key spawn, and `T` a torch; `L` is a locked door,
+// `%` a cracked wall, `^` a trap (hidden or not), and `_` a pressure plate. The level determines the
+// configuration and the prefabs used.
+//
+// With `--validate`, the maps are checked for playability, and the process exits with an error if any
+// is invalid; for example, `map_harness --validate --stats-only --count 5000` is a suitable CI check.
+//
+// Only the map modules are included; they don't depend on Bevy, so there's no need to split the crate
+// into a library.
+
+// Not all the APIs of the included modules are used here.
+#![allow(dead_code)]
+
+#[path = "../map.rs"]
+mod map;
+#[path = "../map_builder/mod.rs"]
+mod map_builder;
+
+mod prelude {
+    pub use bracket_lib::prelude::*;
+    // Must be kept in sync with the values in the main binary.
+    pub const SCREEN_WIDTH: i32 = 80;
+    pub const SCREEN_HEIGHT: i32 = 50;
+    pub use crate::map::*;
+    pub use crate::map_builder::*;
+}
+
+use prelude::*;
+
+const NUM_TILES: usize = (SCREEN_WIDTH * SCREEN_HEIGHT) as usize;
+
+struct Options {
+    architects: Vec<ArchitectType>,
+    meta_builders: Vec<MetaBuilderType>,
+    seed: u64,
+    count: u64,
+    level: usize,
+    stats_only: bool,
+    validate: bool,
+}
+
+impl Options {
+    fn from_args() -> Self {
+        let mut options = Self {
+            architects: ArchitectType::ALL.to_vec(),
+            meta_builders: Vec::new(),
+            seed: RandomNumberGenerator::new().next_u64(),
+            count: 1,
+            level: 0,
+            stats_only: false,
+            validate: false,
+        };
+
+        let mut args = std::env::args().skip(1);
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--architect" => {
+                    let name = args.next().expect("Missing architect name");
+                    options.architects = match name.as_str() {
+                        "empty" => vec![ArchitectType::Empty],
+                        "rooms" => vec![ArchitectType::Rooms],
+                        "automata" => vec![ArchitectType::CellularAutomata],
+                        "drunkard" => vec![ArchitectType::DrunkardsWalk],
+                        "bsp" => vec![ArchitectType::Bsp],
+                        "maze" => vec![ArchitectType::Maze],
+                        "voronoi" => vec![ArchitectType::Voronoi],
+                        "all" => ArchitectType::ALL.to_vec(),
+                        _ => panic!("Unknown architect: {}", name),
+                    };
+                }
+                "--meta" => {
+                    let names = args.next().expect("Missing meta builder names");
+                    options.meta_builders = names
+                        .split(',')
+                        .map(|name| match name {
+                            "doors" => MetaBuilderType::RoomDoors,
+                            "smooth" => MetaBuilderType::SmoothCaves,
+                            "traps" => MetaBuilderType::Traps,
+                            "cracks" => MetaBuilderType::CrackedWalls,
+                            "cull" => MetaBuilderType::CullUnreachable,
+                            _ => panic!("Unknown meta builder: {}", name),
+                        })
+                        .collect();
+                }
+                "--seed" => {
+                    options.seed = args
+                        .next()
+                        .expect("Missing seed value")
+                        .parse()
+                        .expect("Invalid seed");
+                }
+                "--count" => {
+                    options.count = args
+                        .next()
+                        .expect("Missing count value")
+                        .parse()
+                        .expect("Invalid count");
+                }
+                "--level" => {
+                    options.level = args
+                        .next()
+                        .expect("Missing level value")
+                        .parse()
+                        .expect("Invalid level");
+                }
+                "--stats-only" => options.stats_only = true,
+                "--validate" => options.validate = true,
+                _ => panic!("Unknown option: {}", arg),
+            }
+        }
+
+        options
+    }
+}
+
+struct MapStats {
+    floor_ratio: f32,
+    reachable_tiles: usize,
+    amulet_distance: Option<f32>,
+}
+
+impl MapStats {
+    fn new(mb: &MapBuilder) -> Self {
+        let floor_tiles = mb
+            .map
+            .tiles
+            .iter()
+            .filter(|t| **t == TileType::Floor)
+            .count();
+
+        let dijkstra_map = DijkstraMap::new(
+            SCREEN_WIDTH,
+            SCREEN_HEIGHT,
+            &[mb.map.point2d_to_index(mb.player_start)],
+            &mb.map,
+            MAX_PATH_DEPTH,
+        );
+
+        let reachable_tiles = dijkstra_map
+            .map
+            .iter()
+            .filter(|distance| **distance < f32::MAX)
+            .count();
+
+        let amulet_distance = dijkstra_map.map[mb.map.point2d_to_index(mb.amulet_start)];
+        let amulet_distance = (amulet_distance < f32::MAX).then_some(amulet_distance);
+
+        Self {
+            floor_ratio: floor_tiles as f32 / NUM_TILES as f32,
+            reachable_tiles,
+            amulet_distance,
+        }
+    }
+}
+
+fn render_ascii(mb: &MapBuilder) -> String {
+    let mut output = String::with_capacity(NUM_TILES + SCREEN_HEIGHT as usize);
+
+    for y in 0..SCREEN_HEIGHT {
+        for x in 0..SCREEN_WIDTH {
+            let pt = Point::new(x, y);
+            let c = if pt == mb.player_start {
+                '@'
+            } else if pt == mb.amulet_start {
+                'A'
+            } else if mb.monster_spawns.contains(&pt) {
+                'M'
+            } else if mb.item_spawns.contains(&pt) {
+                'I'
+            } else if mb.key_spawns.contains(&pt) {
+                'K'
+            } else if mb.torch_spawns.contains(&pt) {
+                'T'
+            } else {
+                match mb.map.tiles[map_idx(x, y)] {
+                    TileType::Floor => '.',
+                    TileType::Wall => '#',
+                    TileType::Exit => '>',
+                    TileType::StairsUp => '<',
+                    TileType::Door => '+',
+                    TileType::OpenDoor => '\'',
+                    TileType::LockedDoor => 'L',
+                    TileType::CrackedWall => '%',
+                    TileType::Trap | TileType::HiddenTrap => '^',
+                    TileType::PressurePlate => '_',
+                }
+            };
+            output.push(c);
+        }
+        output.push('\n');
+    }
+
+    output
+}
+
+fn main() {
+    let options = Options::from_args();
+    let prefabs = Prefabs::load();
+    let levels_config = LevelsConfig::load();
+    let level_config = levels_config.level(options.level);
+    let mut total_invalid = 0;
+
+    for architect_type in &options.architects {
+        let mut invalid = 0;
+
+        for i in 0..options.count {
+            let seed = options.seed.wrapping_add(i);
+            let mut rng = RandomNumberGenerator::seeded(seed);
+            let mb = MapBuilder::with_architect(
+                *architect_type,
+                &options.meta_builders,
+                options.level,
+                level_config,
+                &prefabs,
+                &mut rng,
+            );
+            let stats = MapStats::new(&mb);
+
+            if !options.stats_only {
+                println!("{}", render_ascii(&mb));
+            }
+
+            let amulet_distance = stats
+                .amulet_distance
+                .map_or("unreachable".to_string(), |distance| {
+                    format!("{:.0}", distance)
+                });
+
+            println!(
+                "architect: {:?}, seed: {}, floor ratio: {:.2}, reachable tiles: {}, amulet distance: {}, monster spawns: {}",
+                architect_type,
+                seed,
+                stats.floor_ratio,
+                stats.reachable_tiles,
+                amulet_distance,
+                mb.monster_spawns.len()
+            );
+
+            if options.validate {
+                if let Err(error) = mb.validate(level_config) {
+                    println!(
+                        "INVALID map (architect: {:?}, seed: {}): {}",
+                        architect_type, seed, error
+                    );
+                    invalid += 1;
+                }
+            }
+        }
+
+        if options.validate {
+            println!(
+                "architect: {:?}, maps: {}, invalid: {}",
+                architect_type, options.count, invalid
+            );
+        }
+        total_invalid += invalid;
+    }
+
+    if total_invalid > 0 {
+        std::process::exit(1);
+    }
+}
@@ -1,4 +1,5 @@
 use crate::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 
 // Every component needs to be derived, so for external types, a wrapper type is needed.
@@ -22,8 +23,23 @@ pub struct Enemy;
 #[derive(Component)]
 pub struct Item;
 
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum EquipmentSlot {
+    Weapon,
+    Armor,
+    Shield,
+    Ring,
+    Light,
+}
+
+#[derive(Component)]
+pub struct Equippable {
+    pub slot: EquipmentSlot,
+}
+
+// Set on equippable items that are currently equipped by their carrier.
 #[derive(Component)]
-pub struct Weapon;
+pub struct Equipped;
 
 #[derive(Component)]
 pub struct AmuletOfYala;
@@ -36,12 +52,109 @@ pub struct ProvidesHealing {
 #[derive(Component)]
 pub struct ProvidesDungeonMap;
 
+// Restores the given amount of food (see Hunger).
+#[derive(Component)]
+pub struct ProvidesFood {
+    pub amount: i32,
+}
+
+// Identifies all the items carried by the user.
+#[derive(Component)]
+pub struct ProvidesIdentification;
+
+// Opens a locked door (and is consumed), when the carrier walks into it.
+#[derive(Component)]
+pub struct Key;
+
+// Lights the area around it, when on the map, or around the carrier, when equipped.
+#[derive(Component)]
+pub struct LightSource {
+    pub radius: i32,
+}
+
+// Turns left before the light source burns out; it's consumed only while equipped.
+#[derive(Component)]
+pub struct Fuel(pub i32);
+
+// Below this, the light source flickers, and lights only half of its radius.
+pub const LOW_FUEL: i32 = 30;
+
+impl LightSource {
+    pub fn effective_radius(&self, fuel: Option<&Fuel>) -> i32 {
+        match fuel {
+            Some(fuel) if fuel.0 < LOW_FUEL => i32::max(1, self.radius / 2),
+            _ => self.radius,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct ProvidesStatusEffects(pub Vec<StatusEffect>);
+
+// Status effects applied to the victim, on a successful (damaging) attack.
+#[derive(Component)]
+pub struct InflictsStatusEffects(pub Vec<StatusEffect>);
+
+// Items that are used on a target tile (ranged weapons, throwables and spells).
+#[derive(Component)]
+pub struct Ranged {
+    pub range: i32,
+}
+
+#[derive(Component)]
+pub struct AreaOfEffect {
+    pub radius: i32,
+}
+
 #[derive(Component)]
 pub struct MovingRandomly;
 
 #[derive(Component)]
 pub struct ChasingPlayer;
 
+// Flees from the player when the health is at or below the threshold; takes priority over the other
+// behaviors.
+#[derive(Component)]
+pub struct FleesWhenHurt {
+    pub health_threshold: i32,
+}
+
+impl FleesWhenHurt {
+    pub fn is_fleeing(&self, health: &Health) -> bool {
+        health.current <= self.health_threshold
+    }
+}
+
+// Attacks from a distance, keeping away from the player.
+#[derive(Component)]
+pub struct Kiting {
+    pub range: i32,
+}
+
+// Stays at its post, chasing the player only while they're within the radius.
+#[derive(Component)]
+pub struct Guarding {
+    pub post: Point,
+    pub radius: i32,
+}
+
+// Walks between the waypoints of the map (see Map::patrol_points), until the player is seen.
+#[derive(Component, Default)]
+pub struct Patrolling {
+    // Remaining steps to the current waypoint; the next one is the last.
+    pub path: Vec<Point>,
+}
+
+// Chases the player when any nearby member of the pack sees them.
+#[derive(Component)]
+pub struct PackMember;
+
+// Doesn't act until woken up by noise (the player's footsteps, or fights) within the hearing radius.
+#[derive(Component)]
+pub struct Asleep {
+    pub hearing: i32,
+}
+
 #[derive(Component)]
 pub struct Health {
     pub current: i32,
@@ -51,6 +164,33 @@ pub struct Health {
 #[derive(Component)]
 pub struct Name(pub String);
 
+// Flavor text, shown when inspecting an item.
+#[derive(Component)]
+pub struct Description(pub String);
+
+// The character progression of the player.
+#[derive(Component)]
+pub struct Experience {
+    pub level: i32,
+    pub xp: i32,
+}
+
+impl Experience {
+    // Total experience required to reach the next level.
+    //
+    pub fn next_level_xp(&self) -> i32 {
+        15 * self.level * (self.level + 1) / 2
+    }
+
+    pub fn can_level_up(&self) -> bool {
+        self.xp >= self.next_level_xp()
+    }
+}
+
+// Experience awarded to the player for killing the entity.
+#[derive(Component)]
+pub struct ExperienceValue(pub i32);
+
 #[derive(Component)]
 pub struct Carried(pub Entity);
 
@@ -58,11 +198,46 @@ pub struct Carried(pub Entity);
 pub struct ActivateItem {
     pub used_by: Entity,
     pub item: Entity,
+    // Set for ranged items; their effects apply to the target area, rather than to the user.
+    pub target: Option<Point>,
 }
 
 #[derive(Component)]
 pub struct Damage(pub i32);
 
+// Subtracted from the damage received; applies to both items (when equipped) and creatures.
+#[derive(Component)]
+pub struct Armor(pub i32);
+
+// Actors gain energy at every round, according to their speed, and spend ACTION_COST for each action; as
+// a consequence, NORMAL_SPEED actors act once per round, faster ones more often, and slower ones less.
+//
+pub const ACTION_COST: i32 = 100;
+pub const NORMAL_SPEED: i32 = 100;
+
+#[derive(Component)]
+pub struct Speed(pub i32);
+
+impl Speed {
+    pub fn effective(&self, status_effects: &StatusEffects) -> i32 {
+        let mut speed = self.0;
+        if status_effects.has(StatusEffectKind::Haste) {
+            speed *= 2;
+        }
+        if status_effects.has(StatusEffectKind::Slow) {
+            speed /= 2;
+        }
+        speed
+    }
+}
+
+#[derive(Component)]
+pub struct Energy(pub i32);
+
+// Set on the monsters that act in the current monsters turn.
+#[derive(Component)]
+pub struct Acting;
+
 #[derive(Component)]
 pub struct FieldOfView {
     pub visible_tiles: HashSet<Point>,
@@ -87,3 +262,126 @@ impl FieldOfView {
         }
     }
 }
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum StatusEffectKind {
+    Poison,
+    Regeneration,
+    Confusion,
+    Haste,
+    Slow,
+    Strength,
+}
+
+impl StatusEffectKind {
+    // Parses the names used by the templates (`provides` and `inflicts` keys).
+    //
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Poison" => Some(Self::Poison),
+            "Regeneration" => Some(Self::Regeneration),
+            "Confusion" => Some(Self::Confusion),
+            "Haste" => Some(Self::Haste),
+            "Slow" => Some(Self::Slow),
+            "Strength" => Some(Self::Strength),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Poison => "Poisoned",
+            Self::Regeneration => "Regenerating",
+            Self::Confusion => "Confused",
+            Self::Haste => "Hasted",
+            Self::Slow => "Slowed",
+            Self::Strength => "Strong",
+        }
+    }
+}
+
+pub const MAX_FOOD: i32 = 1500;
+pub const STARTING_FOOD: i32 = 1000;
+const WELL_FED_FOOD: i32 = 1000;
+const HUNGRY_FOOD: i32 = 250;
+
+// Food left to the player; it decreases by one on each player turn, and when it runs out, the player
+// starves (see end_turn).
+#[derive(Component)]
+pub struct Hunger {
+    pub food: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HungerState {
+    WellFed,
+    Normal,
+    Hungry,
+    Starving,
+}
+
+impl Hunger {
+    pub fn state(&self) -> HungerState {
+        if self.food > WELL_FED_FOOD {
+            HungerState::WellFed
+        } else if self.food > HUNGRY_FOOD {
+            HungerState::Normal
+        } else if self.food > 0 {
+            HungerState::Hungry
+        } else {
+            HungerState::Starving
+        }
+    }
+}
+
+impl HungerState {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::WellFed => "Well Fed",
+            Self::Normal => "",
+            Self::Hungry => "Hungry",
+            Self::Starving => "Starving",
+        }
+    }
+}
+
+// The duration is expressed in rounds (player + monsters turn).
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct StatusEffect {
+    pub kind: StatusEffectKind,
+    pub turns: i32,
+}
+
+// Active status effects of a creature; all the creatures have it, so that effects can be applied
+// without inserting components.
+//
+#[derive(Component, Default)]
+pub struct StatusEffects(pub Vec<StatusEffect>);
+
+impl StatusEffects {
+    // An effect of an already active kind restarts it, if longer, rather than stacking.
+    //
+    pub fn add(&mut self, effect: StatusEffect) {
+        if let Some(active) = self.0.iter_mut().find(|active| active.kind == effect.kind) {
+            active.turns = active.turns.max(effect.turns);
+        } else {
+            self.0.push(effect);
+        }
+    }
+
+    pub fn turns_left(&self, kind: StatusEffectKind) -> Option<i32> {
+        self.0
+            .iter()
+            .find(|effect| effect.kind == kind)
+            .map(|effect| effect.turns)
+    }
+
+    pub fn has(&self, kind: StatusEffectKind) -> bool {
+        self.turns_left(kind).is_some()
+    }
+
+    pub fn tick(&mut self) {
+        self.0.iter_mut().for_each(|effect| effect.turns -= 1);
+        self.0.retain(|effect| effect.turns > 0);
+    }
+}
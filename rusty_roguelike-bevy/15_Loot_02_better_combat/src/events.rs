@@ -17,4 +17,6 @@ pub struct WantsToMove {
 pub struct WantsToAttack {
     pub attacker: Entity,
     pub victim: Entity,
+    // Ranged attacks use the damage of the equipped ranged weapon, if any, rather than the melee one.
+    pub ranged: bool,
 }
@@ -0,0 +1,66 @@
+use crate::components::Name;
+use crate::prelude::*;
+
+// Older messages are discarded.
+const MAX_ENTRIES: usize = 500;
+
+pub struct LogEntry {
+    pub text: String,
+    pub color: (u8, u8, u8),
+    // Repetitions of the same message, in a row, are merged.
+    pub count: usize,
+}
+
+impl LogEntry {
+    pub fn display_text(&self) -> String {
+        if self.count > 1 {
+            format!("{} x{}", self.text, self.count)
+        } else {
+            self.text.clone()
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct GameLog {
+    pub entries: Vec<LogEntry>,
+    // Number of entries scrolled back, in the full history view.
+    pub scroll_offset: usize,
+}
+
+impl GameLog {
+    pub fn add(&mut self, text: impl Into<String>, color: (u8, u8, u8)) {
+        let text = text.into();
+
+        if let Some(last_entry) = self.entries.last_mut() {
+            if last_entry.text == text && last_entry.color == color {
+                last_entry.count += 1;
+                return;
+            }
+        }
+
+        self.entries.push(LogEntry {
+            text,
+            color,
+            count: 1,
+        });
+
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.remove(0);
+        }
+    }
+
+    // The most recent n entries, from the oldest.
+    //
+    pub fn last_entries(&self, n: usize) -> &[LogEntry] {
+        &self.entries[self.entries.len().saturating_sub(n)..]
+    }
+}
+
+// Convenience for composing messages.
+//
+pub fn entity_name(entity: Entity, names_query: &Query<&Name>) -> String {
+    names_query
+        .get(entity)
+        .map_or_else(|_| "creature".to_string(), |name| name.0.clone())
+}
@@ -0,0 +1,95 @@
+use crate::prelude::*;
+use ron::de::from_reader;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+
+// Classes of the items that are unidentified until used; each class has its own pool of appearances.
+#[derive(Clone, Copy, Deserialize, Debug, PartialEq)]
+pub enum ItemClass {
+    Potion,
+    Scroll,
+}
+
+#[derive(Deserialize)]
+struct AppearancesConfig {
+    potions: Vec<String>,
+    scrolls: Vec<String>,
+}
+
+impl AppearancesConfig {
+    fn load() -> Self {
+        let file = File::open("resources/appearances.ron").expect("Failed opening file");
+        from_reader(file).expect("Unable to load appearances")
+    }
+}
+
+// The appearances of the unidentified items ("Murky Potion"), which are randomized on each run, and the
+// items identified so far. Items are referred to by their (true) name, since all the items with the same
+// name look the same.
+//
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Identification {
+    appearances: HashMap<String, String>,
+    identified: HashSet<String>,
+}
+
+impl Identification {
+    // Each item gets a distinct appearance of its class; the items must be in a stable order, so that the
+    // appearances can be reproduced from the run seed.
+    //
+    pub fn new(items: &[(String, ItemClass)], rng: &mut RandomNumberGenerator) -> Self {
+        let config = AppearancesConfig::load();
+        let mut potions = config
+            .potions
+            .iter()
+            .map(|adjective| format!("{} Potion", adjective))
+            .collect::<Vec<_>>();
+        let mut scrolls = config
+            .scrolls
+            .iter()
+            .map(|label| format!("Scroll labeled {}", label))
+            .collect::<Vec<_>>();
+
+        let mut appearances = HashMap::new();
+
+        for (name, class) in items {
+            let pool = match class {
+                ItemClass::Potion => &mut potions,
+                ItemClass::Scroll => &mut scrolls,
+            };
+            if pool.is_empty() {
+                panic!("Not enough appearances for the {:?} items", class);
+            }
+            let appearance = pool.remove(rng.range(0, pool.len()));
+            appearances.insert(name.clone(), appearance);
+        }
+
+        Self {
+            appearances,
+            identified: HashSet::new(),
+        }
+    }
+
+    pub fn is_identified(&self, name: &str) -> bool {
+        !self.appearances.contains_key(name) || self.identified.contains(name)
+    }
+
+    pub fn display_name(&self, name: &str) -> String {
+        if self.is_identified(name) {
+            name.to_string()
+        } else {
+            self.appearances[name].clone()
+        }
+    }
+
+    // Returns whether the item was unidentified.
+    //
+    pub fn identify(&mut self, name: &str) -> bool {
+        let was_unidentified = !self.is_identified(name);
+        if was_unidentified {
+            self.identified.insert(name.to_string());
+        }
+        was_unidentified
+    }
+}
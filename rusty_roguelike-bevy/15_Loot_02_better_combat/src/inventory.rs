@@ -0,0 +1,155 @@
+use crate::components::Name;
+use crate::prelude::*;
+
+pub type CarriedItemsQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static Name,
+        &'static Carried,
+        Option<&'static Equippable>,
+        Option<&'static Equipped>,
+    ),
+    With<Item>,
+>;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum InventorySort {
+    ByName,
+    // Equipment first (by slot), then consumables.
+    ByType,
+}
+
+impl InventorySort {
+    pub fn next(self) -> Self {
+        match self {
+            InventorySort::ByName => InventorySort::ByType,
+            InventorySort::ByType => InventorySort::ByName,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            InventorySort::ByName => "name",
+            InventorySort::ByType => "type",
+        }
+    }
+}
+
+// State of the inventory screen (TurnState::ShowingInventory). It's kept when the screen is closed, so
+// that the sort order also applies to the HUD list and the number keys.
+//
+pub struct InventoryScreen {
+    pub selected: usize,
+    pub sort: InventorySort,
+}
+
+impl Default for InventoryScreen {
+    fn default() -> Self {
+        Self {
+            selected: 0,
+            sort: InventorySort::ByName,
+        }
+    }
+}
+
+// Identical consumables are stacked into a single entry; equipment is listed individually, since each
+// item is equipped separately. Unidentified items are named after their appearance.
+//
+pub struct InventoryEntry {
+    pub name: String,
+    pub identified: bool,
+    pub items: Vec<Entity>,
+    pub slot: Option<EquipmentSlot>,
+    pub equipped: bool,
+}
+
+impl InventoryEntry {
+    // The item used (or dropped) when the entry is selected.
+    //
+    pub fn item(&self) -> Entity {
+        self.items[0]
+    }
+
+    pub fn display_text(&self) -> String {
+        let mut text = self.name.clone();
+        if self.items.len() > 1 {
+            text += &format!(" x{}", self.items.len());
+        }
+        if self.equipped {
+            text += " (equipped)";
+        }
+        text
+    }
+
+    fn type_rank(&self) -> usize {
+        match self.slot {
+            Some(EquipmentSlot::Weapon) => 0,
+            Some(EquipmentSlot::Armor) => 1,
+            Some(EquipmentSlot::Shield) => 2,
+            Some(EquipmentSlot::Ring) => 3,
+            Some(EquipmentSlot::Light) => 4,
+            None => 5,
+        }
+    }
+}
+
+// The items carried by the carrier, in a stable order; the ECS iteration order is not, since it changes
+// as components are added and removed.
+//
+pub fn carried_items(
+    carrier: Entity,
+    items_query: &CarriedItemsQuery,
+    identification: &Identification,
+    sort: InventorySort,
+) -> Vec<InventoryEntry> {
+    let mut items = items_query
+        .iter()
+        .filter(|(_, _, carried, _, _)| carried.0 == carrier)
+        .collect::<Vec<_>>();
+    // The entity ids break the ties, so that the order within the stacks is deterministic as well.
+    items.sort_by_key(|(entity, _, _, _, _)| *entity);
+
+    let mut entries = Vec::<InventoryEntry>::new();
+
+    for (entity, name, _, equippable, equipped) in items {
+        let display_name = identification.display_name(&name.0);
+        let stack_index = if equippable.is_none() {
+            entries
+                .iter()
+                .position(|entry| entry.slot.is_none() && entry.name == display_name)
+        } else {
+            None
+        };
+
+        if let Some(stack_index) = stack_index {
+            entries[stack_index].items.push(entity);
+        } else {
+            entries.push(InventoryEntry {
+                name: display_name,
+                identified: identification.is_identified(&name.0),
+                items: vec![entity],
+                slot: equippable.map(|e| e.slot),
+                equipped: equipped.is_some(),
+            });
+        }
+    }
+
+    // The sort is stable; equipped items come before identical unequipped ones.
+    match sort {
+        InventorySort::ByName => entries.sort_by(|a, b| {
+            a.name
+                .cmp(&b.name)
+                .then_with(|| b.equipped.cmp(&a.equipped))
+        }),
+        InventorySort::ByType => entries.sort_by(|a, b| {
+            a.type_rank()
+                .cmp(&b.type_rank())
+                .then_with(|| a.name.cmp(&b.name))
+                .then_with(|| b.equipped.cmp(&a.equipped))
+        }),
+    }
+
+    entries
+}
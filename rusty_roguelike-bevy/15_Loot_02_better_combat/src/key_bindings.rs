@@ -0,0 +1,160 @@
+use crate::prelude::*;
+use ron::de::from_reader;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs::File;
+
+const KEY_BINDINGS_FILE: &str = "resources/key_bindings.ron";
+
+// The commands available while awaiting the player input. The modal screens (targeting, inventory,
+// message log) have their own, fixed, keys.
+//
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+pub enum Action {
+    // Delta (x, y).
+    Move(i32, i32),
+    // Skips the turn; health is recovered if no enemy is in sight.
+    Wait,
+    PickUp,
+    // Walks towards the nearest unexplored area, until something interesting happens.
+    Explore,
+    Fire,
+    // Looks for hidden traps around the player.
+    Search,
+    // Closes an adjacent open door.
+    CloseDoor,
+    // Index in the list of carried items.
+    UseItem(usize),
+    Inventory,
+    MessageLog,
+    Descend,
+    Ascend,
+    SaveAndQuit,
+}
+
+// Sets of movement keys. The cursor keys are always available.
+//
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub enum MovementPreset {
+    // Diagonals on Home/PageUp/End/PageDown.
+    Arrows,
+    ViKeys,
+    Numpad,
+}
+
+#[derive(Deserialize)]
+struct KeyBindingsConfig {
+    movement: MovementPreset,
+    // (key name, action); they take priority over the movement preset.
+    bindings: Vec<(String, Action)>,
+}
+
+pub struct KeyBindings {
+    actions: HashMap<VirtualKeyCode, Action>,
+    // In the configuration order, so that the first key bound to an action is the one shown.
+    key_names: Vec<(String, Action)>,
+}
+
+impl KeyBindings {
+    pub fn load() -> Self {
+        let file = File::open(KEY_BINDINGS_FILE).expect("Failed opening file");
+        let config: KeyBindingsConfig = from_reader(file).expect("Unable to load key bindings");
+
+        let mut actions = movement_keys(config.movement)
+            .into_iter()
+            .map(|(key, (dx, dy))| (key, Action::Move(dx, dy)))
+            .collect::<HashMap<_, _>>();
+
+        for (key_name, action) in config.bindings.iter() {
+            let key = key_code(key_name)
+                .unwrap_or_else(|| panic!("Unknown key in the key bindings: {}", key_name));
+            actions.insert(key, *action);
+        }
+
+        Self {
+            actions,
+            key_names: config.bindings,
+        }
+    }
+
+    pub fn action(&self, key: VirtualKeyCode) -> Option<Action> {
+        self.actions.get(&key).copied()
+    }
+
+    // The name of the (first) key bound to the action, as shown to the player; the digit keys are shown
+    // without the `Key` prefix.
+    //
+    pub fn key_name(&self, action: Action) -> Option<&str> {
+        self.key_names
+            .iter()
+            .find(|(_, bound_action)| *bound_action == action)
+            .map(|(name, _)| match name.strip_prefix("Key") {
+                Some(digit) if !digit.is_empty() => digit,
+                _ => name.as_str(),
+            })
+    }
+}
+
+fn movement_keys(preset: MovementPreset) -> Vec<(VirtualKeyCode, (i32, i32))> {
+    use VirtualKeyCode::*;
+
+    let mut keys = vec![
+        (Left, (-1, 0)),
+        (Right, (1, 0)),
+        (Up, (0, -1)),
+        (Down, (0, 1)),
+    ];
+
+    match preset {
+        MovementPreset::Arrows => keys.extend([
+            (Home, (-1, -1)),
+            (PageUp, (1, -1)),
+            (End, (-1, 1)),
+            (PageDown, (1, 1)),
+        ]),
+        MovementPreset::ViKeys => keys.extend([
+            (H, (-1, 0)),
+            (L, (1, 0)),
+            (K, (0, -1)),
+            (J, (0, 1)),
+            (Y, (-1, -1)),
+            (U, (1, -1)),
+            (B, (-1, 1)),
+            (N, (1, 1)),
+        ]),
+        MovementPreset::Numpad => keys.extend([
+            (Numpad4, (-1, 0)),
+            (Numpad6, (1, 0)),
+            (Numpad8, (0, -1)),
+            (Numpad2, (0, 1)),
+            (Numpad7, (-1, -1)),
+            (Numpad9, (1, -1)),
+            (Numpad1, (-1, 1)),
+            (Numpad3, (1, 1)),
+        ]),
+    }
+
+    keys
+}
+
+// The key names are the VirtualKeyCode variant names; only the keys that make sense for the game are
+// supported.
+//
+fn key_code(name: &str) -> Option<VirtualKeyCode> {
+    macro_rules! key_names {
+        ($($key:ident),*) => {
+            match name {
+                $(stringify!($key) => Some(VirtualKeyCode::$key),)*
+                _ => None,
+            }
+        };
+    }
+
+    key_names!(
+        Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9, Key0, A, B, C, D, E, F, G, H, I, J,
+        K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z, Numpad0, Numpad1, Numpad2, Numpad3,
+        Numpad4, Numpad5, Numpad6, Numpad7, Numpad8, Numpad9, Left, Right, Up, Down, Home, End,
+        PageUp, PageDown, Insert, Delete, Space, Return, Tab, Back, Period, Comma, Slash,
+        Semicolon, Minus, Equals, Apostrophe, LBracket, RBracket, Backslash, Grave
+    )
+}
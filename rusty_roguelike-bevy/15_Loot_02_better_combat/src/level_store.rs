@@ -0,0 +1,18 @@
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// The levels visited, but not currently played. Their entities live outside the world, in the same
+// representation used by the save files, so that they're not processed by the systems.
+//
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct LevelStore {
+    pub levels: HashMap<u32, StoredLevel>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct StoredLevel {
+    pub map: Map,
+    pub theme: String,
+    pub entities: Vec<SavedEntity>,
+}
@@ -0,0 +1,68 @@
+use crate::prelude::*;
+
+const NUM_TILES: usize = (SCREEN_WIDTH * SCREEN_HEIGHT) as usize;
+
+// Below this intensity, a tile is dark: what's on it can't be seen, even if it's in the field of view.
+const DARKNESS_THRESHOLD: f32 = 0.15;
+// Visible tiles are never rendered darker than this, so that the layout can still be made out.
+const MIN_BRIGHTNESS: f32 = 0.3;
+
+// Light intensity (0.0 to 1.0) of each tile of the current level; it's recomputed on every frame by the
+// lighting system, from the level ambient light and the light sources.
+//
+pub struct LightMap {
+    pub intensity: Vec<f32>,
+}
+
+impl Default for LightMap {
+    fn default() -> Self {
+        Self {
+            intensity: vec![1.0; NUM_TILES],
+        }
+    }
+}
+
+impl LightMap {
+    pub fn reset(&mut self, ambient_light: f32) {
+        self.intensity.iter_mut().for_each(|i| *i = ambient_light);
+    }
+
+    // The light fades linearly with the distance from the source; overlapping lights don't add up.
+    //
+    pub fn add_light(&mut self, source: Point, radius: i32, map: &Map) {
+        for pt in field_of_view_set(source, radius, map) {
+            if let Some(idx) = map.try_idx(pt) {
+                let distance = DistanceAlg::Pythagoras.distance2d(source, pt);
+                let intensity = 1.0 - distance / (radius + 1) as f32;
+                self.intensity[idx] = self.intensity[idx].max(intensity);
+            }
+        }
+    }
+
+    pub fn is_lit(&self, pt: Point) -> bool {
+        self.intensity[map_idx(pt.x, pt.y)] >= DARKNESS_THRESHOLD
+    }
+
+    // Whether what's on the given tile can be seen by the observer; creatures next to the observer are
+    // always noticed, even in the dark.
+    //
+    pub fn can_see(&self, observer_pos: Point, observer_fov: &FieldOfView, pt: Point) -> bool {
+        observer_fov.visible_tiles.contains(&pt)
+            && (self.is_lit(pt)
+                || DistanceAlg::Pythagoras.distance2d(observer_pos, pt) <= MELEE_RANGE)
+    }
+
+    pub fn shade(&self, pt: Point, colors: ColorPair) -> ColorPair {
+        let brightness = self.intensity[map_idx(pt.x, pt.y)].clamp(MIN_BRIGHTNESS, 1.0);
+        let scale = |color: RGBA| {
+            RGBA::from_f32(
+                color.r * brightness,
+                color.g * brightness,
+                color.b * brightness,
+                color.a,
+            )
+        };
+
+        ColorPair::new(scale(colors.fg), scale(colors.bg))
+    }
+}
@@ -1,12 +1,25 @@
+mod auto_move;
 mod camera;
 mod components;
 mod events;
+mod game_log;
 mod game_stage;
+mod identification;
+mod inventory;
+mod key_bindings;
+mod level_store;
+mod lighting;
 mod map;
 mod map_builder;
+mod morgue;
+mod progression;
+mod run_seed;
+mod run_stats;
+mod save;
 mod spawner;
 mod state_label;
 mod systems;
+mod targeting;
 mod turn_state;
 
 mod prelude {
@@ -18,15 +31,28 @@ mod prelude {
     pub const SCREEN_HEIGHT: i32 = 50;
     pub const DISPLAY_WIDTH: i32 = SCREEN_WIDTH / 2;
     pub const DISPLAY_HEIGHT: i32 = SCREEN_HEIGHT / 2;
+    pub use crate::auto_move::*;
     pub use crate::camera::*;
     pub use crate::components::*;
     pub use crate::events::*;
+    pub use crate::game_log::*;
     pub use crate::game_stage::*;
+    pub use crate::identification::*;
+    pub use crate::inventory::*;
+    pub use crate::key_bindings::*;
+    pub use crate::level_store::*;
+    pub use crate::lighting::*;
     pub use crate::map::*;
     pub use crate::map_builder::*;
+    pub use crate::morgue::*;
+    pub use crate::progression::*;
+    pub use crate::run_seed::*;
+    pub use crate::run_stats::*;
+    pub use crate::save::*;
     pub use crate::spawner::*;
     pub use crate::state_label::*;
     pub use crate::systems::*;
+    pub use crate::targeting::*;
     pub use crate::turn_state::*;
 }
 
@@ -34,23 +60,46 @@ use prelude::*;
 
 struct State {
     ecs: App,
+    // Shown on startup when a saved run is available; the game is not updated until an option is picked.
+    show_startup_menu: bool,
+    load_error: Option<String>,
+    // Passed via `--seed`; when set, the new runs (including the ones started after the end of a run) use
+    // it, rather than a random one.
+    cli_seed: Option<RunSeed>,
 }
 
 impl State {
-    fn new() -> Self {
+    fn new(cli_seed: Option<RunSeed>) -> Self {
         use game_stage::GameStage::*;
 
+        let seed = cli_seed.unwrap_or_else(RunSeed::random);
         let mut ecs = App::new();
-        let mut rng = RandomNumberGenerator::new();
-        let mut map_builder = MapBuilder::new(&mut rng);
+        // The configuration files are loaded once, so that invalid ones are reported at startup.
+        let themes = Themes::load();
+        let levels_config = LevelsConfig::load();
+        levels_config.check_themes(&themes);
+        let templates = Templates::load();
+        let identification = randomize_appearances(&templates, &mut seed.appearance_rng());
+        ecs.insert_resource(themes);
+        ecs.insert_resource(levels_config);
+        ecs.insert_resource(Prefabs::load());
+        ecs.insert_resource(templates);
+        let mut rng = seed.level_rng(0);
+        let map_builder = generate_level(&mut ecs.world, &mut rng, 0);
         // This is not a strict-ECS approach (a system would), but we mimick the source project design.
         spawn_player(&mut ecs.world, map_builder.player_start);
-        //spawn_amulet_of_yala(&mut ecs, map_builder.amulet_start);
-        let exit_idx = map_builder.map.point2d_to_index(map_builder.amulet_start);
-        map_builder.map.tiles[exit_idx] = TileType::Exit;
-        spawn_level(&mut ecs.world, &mut rng, 0, &map_builder.monster_spawns);
         ecs.insert_resource(map_builder.map);
         ecs.insert_resource(Camera::new(map_builder.player_start));
+        // The same RNG is then used by the systems (e.g. monster AI), so that a run is entirely reproducible.
+        ecs.insert_resource(rng);
+        ecs.insert_resource(identification);
+        ecs.insert_resource(seed);
+        ecs.insert_resource(LevelStore::default());
+        ecs.insert_resource(RunStats::default());
+        ecs.insert_resource(GameLog::default());
+        ecs.insert_resource(InventoryScreen::default());
+        ecs.insert_resource(KeyBindings::load());
+        ecs.insert_resource(LightMap::default());
         // In Bevy, it's necessary to register the event types.
         ecs.add_event::<WantsToMove>();
         ecs.add_event::<WantsToAttack>();
@@ -69,7 +118,49 @@ impl State {
         // In the source project, set of actions (`Schedule`s) are owned by State (`systems: Schedule`);
         // here, they're owned by the Bevy ECS, as `SystemSet`s.
         build_system_sets(&mut ecs);
-        Self { ecs }
+        Self {
+            ecs,
+            show_startup_menu: save_exists(),
+            load_error: None,
+            cli_seed,
+        }
+    }
+
+    fn startup_menu(&mut self, ctx: &mut BTerm) {
+        ctx.set_active_console(2);
+        ctx.print_color_centered(2, YELLOW, BLACK, "Dungeon Crawler");
+        ctx.print_color_centered(5, GREEN, BLACK, "Press 1 to continue the saved run.");
+        ctx.print_color_centered(6, GREEN, BLACK, "Press 2 to start a new run.");
+
+        if let Some(load_error) = &self.load_error {
+            ctx.print_color_centered(9, RED, BLACK, format!("Unable to load: {}", load_error));
+        }
+
+        match ctx.key {
+            Some(VirtualKeyCode::Key1) => match load_game(&mut self.ecs.world) {
+                Ok(()) => {
+                    // As in traditional roguelikes, a run can be resumed only once.
+                    delete_save();
+                    self.show_startup_menu = false;
+                }
+                Err(error) => self.load_error = Some(error.to_string()),
+            },
+            Some(VirtualKeyCode::Key2) => {
+                // The world is already initialized with a new run.
+                self.show_startup_menu = false;
+            }
+            _ => {}
+        }
+    }
+
+    fn save_and_quit(&mut self, ctx: &mut BTerm) {
+        match save_game(&mut self.ecs.world) {
+            Ok(()) => ctx.quit(),
+            Err(error) => {
+                eprintln!("Unable to save the game: {}", error);
+                self.ecs.insert_resource(TurnState::AwaitingInput);
+            }
+        }
     }
 
     fn reset_game_state(&mut self) {
@@ -81,30 +172,36 @@ impl State {
         // Finally, the resources directly known to us, we just overwrite them.
         // Note that we can also just replace the current app with a new one.
         self.ecs.world.clear_entities();
-        let mut rng = RandomNumberGenerator::new();
-        let mut map_builder = MapBuilder::new(&mut rng);
+        let seed = self.cli_seed.unwrap_or_else(RunSeed::random);
+        let mut rng = seed.level_rng(0);
+        let map_builder = generate_level(&mut self.ecs.world, &mut rng, 0);
         spawn_player(&mut self.ecs.world, map_builder.player_start);
-        let exit_idx = map_builder.map.point2d_to_index(map_builder.amulet_start);
-        map_builder.map.tiles[exit_idx] = TileType::Exit;
-        spawn_level(
-            &mut self.ecs.world,
-            &mut rng,
-            0,
-            &map_builder.monster_spawns,
-        );
         self.ecs.insert_resource(map_builder.map);
         self.ecs
             .insert_resource(Camera::new(map_builder.player_start));
-        self.ecs
-            .insert_resource(TurnState::AwaitingInput);
+        self.ecs.insert_resource(TurnState::AwaitingInput);
         self.ecs.insert_resource(map_builder.theme);
+        let templates = self.ecs.world.get_resource::<Templates>().unwrap();
+        let identification = randomize_appearances(templates, &mut seed.appearance_rng());
+        self.ecs.insert_resource(identification);
+        self.ecs.insert_resource(rng);
+        self.ecs.insert_resource(seed);
+        self.ecs.insert_resource(LevelStore::default());
+        self.ecs.insert_resource(RunStats::default());
+        self.ecs.insert_resource(GameLog::default());
+        self.ecs.insert_resource(InventoryScreen::default());
+        self.ecs.world.remove_resource::<AutoMove>();
+        self.ecs.world.remove_resource::<DeathCause>();
+        self.ecs.world.remove_resource::<HighScores>();
         // Don't forget! :)
         self.ecs.world.remove_resource::<VirtualKeyCode>();
     }
 
-    fn advance_level(&mut self) {
-        let mut player_query = self.ecs.world.query_filtered::<Entity, With<Player>>();
-        let player_entity = player_query.iter(&self.ecs.world).next().unwrap();
+    fn change_level(&mut self, level_delta: i32) {
+        let mut player_query = self.ecs.world.query::<(Entity, &Player)>();
+        let (player_entity, player) = player_query.iter(&self.ecs.world).next().unwrap();
+        let current_level = player.map_level;
+        let map_level = (current_level as i32 + level_delta) as u32;
 
         use std::collections::HashSet;
         let mut entities_to_keep = HashSet::new();
@@ -124,6 +221,24 @@ impl State {
             .iter(&self.ecs.world)
             .filter_map(|e| (!entities_to_keep.contains(&e)).then(|| e))
             .collect::<Vec<_>>();
+
+        // Differently from the source project, levels are persistent; before removing the entities, we
+        // store them, along with the map, so that they can be restored when the player comes back.
+        let stored_level = StoredLevel {
+            map: self.ecs.world.remove_resource::<Map>().unwrap(),
+            theme: self
+                .ecs
+                .world
+                .get_resource::<Box<dyn MapTheme>>()
+                .unwrap()
+                .name()
+                .to_string(),
+            entities: snapshot_entities(&self.ecs.world, &entities_to_remove),
+        };
+        let mut level_store = self.ecs.world.get_resource_mut::<LevelStore>().unwrap();
+        level_store.levels.insert(current_level, stored_level);
+        let stored_level = level_store.levels.remove(&map_level);
+
         // We don't need flushing; when manipulating World directly in Bevy, flushes are implicit.
         for e in entities_to_remove {
             self.ecs.world.despawn(e);
@@ -134,47 +249,126 @@ impl State {
             fov.is_dirty = true;
         }
 
-        let mut rng = RandomNumberGenerator::new();
-        let mut map_builder = MapBuilder::new(&mut rng);
-        let mut map_level = 0;
+        let mut rng = self
+            .ecs
+            .world
+            .get_resource::<RunSeed>()
+            .unwrap()
+            .level_rng(map_level);
+
+        let (map, theme, player_start) = if let Some(stored_level) = stored_level {
+            restore_entities(&mut self.ecs.world, &stored_level.entities);
+
+            // The player arrives on the stairs leading to the level they come from; if they're missing
+            // (e.g. covered by a map feature), on the first enterable tile.
+            let arrival_tile = if map_level > current_level {
+                TileType::StairsUp
+            } else {
+                TileType::Exit
+            };
+            let map = &stored_level.map;
+            let arrival_idx = map
+                .tiles
+                .iter()
+                .position(|t| *t == arrival_tile)
+                .or_else(|| {
+                    (0..map.tiles.len()).find(|idx| map.can_enter_tile(map.index_to_point2d(*idx)))
+                })
+                .unwrap_or(0);
+            let player_start = map.index_to_point2d(arrival_idx);
+            let theme = self
+                .ecs
+                .world
+                .get_resource::<Themes>()
+                .unwrap()
+                .theme(&stored_level.theme)
+                .unwrap();
+
+            (stored_level.map, theme, player_start)
+        } else {
+            let map_builder = generate_level(&mut self.ecs.world, &mut rng, map_level);
+
+            (map_builder.map, map_builder.theme, map_builder.player_start)
+        };
+
         let mut player_query = self.ecs.world.query::<(&mut Player, &mut PointC)>();
         for (mut player, mut pos) in player_query.iter_mut(&mut self.ecs.world) {
-            player.map_level += 1;
-            map_level = player.map_level;
-            pos.0.x = map_builder.player_start.x;
-            pos.0.y = map_builder.player_start.y;
+            player.map_level = map_level;
+            pos.0.x = player_start.x;
+            pos.0.y = player_start.y;
         }
-        if map_level == 2 {
-            spawn_amulet_of_yala(&mut self.ecs.world, map_builder.amulet_start);
+        self.ecs.world.insert_resource(map);
+        self.ecs.world.insert_resource(Camera::new(player_start));
+        self.ecs.insert_resource(TurnState::AwaitingInput);
+        self.ecs.world.insert_resource(theme);
+        self.ecs.world.insert_resource(rng);
+        self.ecs.world.remove_resource::<AutoMove>();
+
+        let mut run_stats = self.ecs.world.get_resource_mut::<RunStats>().unwrap();
+        run_stats.deepest_level = u32::max(run_stats.deepest_level, map_level);
+
+        let message = if map_level > current_level {
+            format!("You descend to level {}.", map_level + 1)
         } else {
-            let exit_idx = map_builder.map.point2d_to_index(map_builder.amulet_start);
-            map_builder.map.tiles[exit_idx] = TileType::Exit;
+            format!("You climb back to level {}.", map_level + 1)
+        };
+        let mut game_log = self.ecs.world.get_resource_mut::<GameLog>().unwrap();
+        game_log.add(message, WHITE);
+    }
+
+    fn print_seed(&self, ctx: &mut BTerm, y: i32) {
+        let seed = self.ecs.world.get_resource::<RunSeed>().unwrap();
+        ctx.print_color_centered(y, GRAY, BLACK, format!("Seed: {}", seed.0));
+    }
+
+    // The run is recorded (morgue file and high scores) on the first frame of its end screen.
+    //
+    fn end_run(&mut self, victory: bool) {
+        if self.ecs.world.get_resource::<HighScores>().is_none() {
+            let high_scores = record_run(&mut self.ecs.world, victory);
+            self.ecs.insert_resource(high_scores);
+        }
+    }
+
+    fn print_high_scores(&self, ctx: &mut BTerm, y: i32) {
+        let high_scores = self.ecs.world.get_resource::<HighScores>().unwrap();
+
+        ctx.print_color_centered(y, YELLOW, BLACK, "High scores");
+        for (i, entry) in high_scores.entries.iter().enumerate() {
+            let color = if high_scores.latest == Some(i) {
+                GREEN
+            } else {
+                WHITE
+            };
+            ctx.print_color_centered(
+                y + 2 + i as i32,
+                color,
+                BLACK,
+                format!(
+                    "{:>2}. {:>5}  {} (depth {}, {} turns)",
+                    i + 1,
+                    entry.score,
+                    entry.outcome,
+                    entry.depth,
+                    entry.turns
+                ),
+            );
         }
-        spawn_level(
-            &mut self.ecs.world,
-            &mut rng,
-            0,
-            &map_builder.monster_spawns,
-        );
-        self.ecs.world.insert_resource(map_builder.map);
-        self.ecs
-            .world
-            .insert_resource(Camera::new(map_builder.player_start));
-        self.ecs
-            .insert_resource(TurnState::AwaitingInput);
-        self.ecs.world.insert_resource(map_builder.theme);
     }
 
     fn game_over(&mut self, ctx: &mut BTerm) {
+        self.end_run(false);
+
         ctx.set_active_console(2);
         ctx.print_color_centered(2, RED, BLACK, "Your quest has ended.");
-        ctx.print_color_centered(
-            4,
-            WHITE,
-            BLACK,
-            "Slain by a monster, your hero's journey has come to a \
-            premature end.",
-        );
+        let death_message = match self.ecs.world.get_resource::<DeathCause>() {
+            Some(cause) => format!(
+                "{}, your hero's journey has come to a premature end.",
+                cause.description()
+            ),
+            None => "Your hero's journey has come to a premature end.".to_string(),
+        };
+        ctx.print_color_centered(4, WHITE, BLACK, death_message);
         ctx.print_color_centered(
             5,
             WHITE,
@@ -189,13 +383,73 @@ impl State {
             "Don't worry, you can always try again with a new hero.",
         );
         ctx.print_color_centered(9, GREEN, BLACK, "Press 1 to play again.");
+        self.print_seed(ctx, 11);
+        self.print_high_scores(ctx, 14);
 
         if let Some(VirtualKeyCode::Key1) = ctx.key {
             self.reset_game_state();
         }
     }
 
+    fn level_up(&mut self, ctx: &mut BTerm) {
+        ctx.set_active_console(2);
+
+        let mut player_query = self.ecs.world.query::<(&Player, &Experience)>();
+        let (_, experience) = player_query.iter(&self.ecs.world).next().unwrap();
+
+        ctx.print_color_centered(
+            2,
+            YELLOW,
+            BLACK,
+            format!("You have reached level {}!", experience.level + 1),
+        );
+        ctx.print_color_centered(
+            4,
+            WHITE,
+            BLACK,
+            "Your health is restored, and you feel stronger and more resilient.",
+        );
+        ctx.print_color_centered(6, WHITE, BLACK, "Choose a perk:");
+        for (i, perk) in Perk::ALL.iter().enumerate() {
+            ctx.print_color_centered(
+                8 + i as i32,
+                GREEN,
+                BLACK,
+                format!("{} : {}", i + 1, perk.description()),
+            );
+        }
+
+        let perk = match ctx.key {
+            Some(VirtualKeyCode::Key1) => Some(Perk::ALL[0]),
+            Some(VirtualKeyCode::Key2) => Some(Perk::ALL[1]),
+            Some(VirtualKeyCode::Key3) => Some(Perk::ALL[2]),
+            Some(VirtualKeyCode::Key4) => Some(Perk::ALL[3]),
+            _ => None,
+        };
+
+        if let Some(perk) = perk {
+            level_up(&mut self.ecs.world, perk);
+
+            let mut game_log = self.ecs.world.get_resource_mut::<GameLog>().unwrap();
+            game_log.add(
+                format!("You feel more experienced ({}).", perk.description()),
+                YELLOW,
+            );
+
+            // Multiple levels can be gained at once; in such case, the screen is shown again.
+            let mut player_query = self.ecs.world.query::<(&Player, &Experience)>();
+            let (_, experience) = player_query.iter(&self.ecs.world).next().unwrap();
+            if !experience.can_level_up() {
+                self.ecs.insert_resource(TurnState::AwaitingInput);
+            }
+            // The key must not be processed by the player input, in this frame.
+            self.ecs.world.remove_resource::<VirtualKeyCode>();
+        }
+    }
+
     fn victory(&mut self, ctx: &mut BTerm) {
+        self.end_run(true);
+
         ctx.set_active_console(2);
         ctx.print_color_centered(2, GREEN, BLACK, "You have won!");
         ctx.print_color_centered(
@@ -218,6 +472,8 @@ impl State {
             "Press 1 to \
             play again.",
         );
+        self.print_seed(ctx, 9);
+        self.print_high_scores(ctx, 12);
         if let Some(VirtualKeyCode::Key1) = ctx.key {
             self.reset_game_state();
         }
@@ -232,6 +488,10 @@ impl GameState for State {
         ctx.cls();
         ctx.set_active_console(2);
         ctx.cls();
+        if self.show_startup_menu {
+            self.startup_menu(ctx);
+            return;
+        }
         if let Some(key) = ctx.key {
             self.ecs.insert_resource(key);
         } else {
@@ -241,12 +501,16 @@ impl GameState for State {
         }
         ctx.set_active_console(0);
         self.ecs.insert_resource(Point::from_tuple(ctx.mouse_pos()));
+        self.ecs.insert_resource(MouseClick(ctx.left_click));
         // Unfortunately, with the current source project's design, without refactoring the world init
         // code into systems, we must leak the state into this abstraction.
         match self.ecs.world.get_resource::<TurnState>() {
             Some(TurnState::GameOver) => self.game_over(ctx),
             Some(TurnState::Victory) => self.victory(ctx),
-            Some(TurnState::NextLevel) => self.advance_level(),
+            Some(TurnState::LevelUp) => self.level_up(ctx),
+            Some(TurnState::NextLevel) => self.change_level(1),
+            Some(TurnState::PreviousLevel) => self.change_level(-1),
+            Some(TurnState::SaveAndQuit) => self.save_and_quit(ctx),
             _ => {}
         }
         self.ecs.update();
@@ -254,6 +518,48 @@ impl GameState for State {
     }
 }
 
+// Generates a new level, and spawns its entities; the map related resources are left to the caller.
+//
+fn generate_level(
+    world: &mut World,
+    rng: &mut RandomNumberGenerator,
+    map_level: u32,
+) -> MapBuilder {
+    let levels_config = world.get_resource::<LevelsConfig>().unwrap();
+    let mut map_builder = MapBuilder::new(
+        rng,
+        map_level as usize,
+        levels_config,
+        world.get_resource::<Prefabs>().unwrap(),
+        world.get_resource::<Themes>().unwrap(),
+    );
+
+    if map_level == levels_config.depth - 1 {
+        spawn_amulet_of_yala(world, map_builder.amulet_start);
+    } else {
+        let exit_idx = map_builder.map.point2d_to_index(map_builder.amulet_start);
+        map_builder.map.tiles[exit_idx] = TileType::Exit;
+    }
+
+    // The first level is the dungeon entrance, so there's nothing above it.
+    if map_level > 0 {
+        let entrance_idx = map_builder.map.point2d_to_index(map_builder.player_start);
+        map_builder.map.tiles[entrance_idx] = TileType::StairsUp;
+    }
+
+    spawn_level(
+        world,
+        rng,
+        map_level as usize,
+        &map_builder.monster_spawns,
+        &map_builder.item_spawns,
+        &map_builder.key_spawns,
+        &map_builder.torch_spawns,
+    );
+
+    map_builder
+}
+
 fn main() -> BError {
     let context = BTermBuilder::new()
         .with_title("Dungeon Crawler")
@@ -268,5 +574,5 @@ fn main() -> BError {
         .with_simple_console_no_bg(SCREEN_WIDTH * 2, SCREEN_HEIGHT * 2, "terminal8x8.png")
         .build()?;
 
-    main_loop(context, State::new())
+    main_loop(context, State::new(RunSeed::from_args()))
 }
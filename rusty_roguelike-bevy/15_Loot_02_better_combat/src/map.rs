@@ -1,21 +1,68 @@
 use crate::prelude::*;
+use serde::{Deserialize, Serialize};
 
 const NUM_TILES: usize = (SCREEN_WIDTH * SCREEN_HEIGHT) as usize;
 
-#[derive(Copy, Clone, PartialEq)]
+// Creatures move in 8 directions; diagonal steps cost slightly more, so that paths don't zigzag.
+pub const DIRECTIONS: [Point; 8] = [
+    Point::constant(-1, 0),
+    Point::constant(1, 0),
+    Point::constant(0, -1),
+    Point::constant(0, 1),
+    Point::constant(-1, -1),
+    Point::constant(1, -1),
+    Point::constant(-1, 1),
+    Point::constant(1, 1),
+];
+const DIAGONAL_COST: f32 = 1.45;
+
+// Maximum distance between two adjacent tiles (including diagonally adjacent ones).
+pub const MELEE_RANGE: f32 = 1.5;
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
 pub enum TileType {
     Wall,
     Floor,
+    // Stairs down.
     Exit,
+    StairsUp,
+    // Closed doors block the sight; creatures open them by walking into them.
+    Door,
+    OpenDoor,
+    // Opened (and turned into an open door) by walking into it with a key.
+    LockedDoor,
+    // Blocks like a wall, but the player can break through it, and explosions destroy it.
+    CrackedWall,
+    // Damages whoever steps on it; hidden traps look like floor, until found by searching or triggered.
+    Trap,
+    HiddenTrap,
+    // Sounds an alarm, which wakes up the sleeping monsters.
+    PressurePlate,
 }
 
+// Traps can be walked through, but the pathing avoids them, when they're known.
+const TRAP_PATHING_COST: f32 = 10.0;
+// Opening a door takes a turn.
+const DOOR_PATHING_COST: f32 = 2.0;
+
+// Depth bound of the Dijkstra maps used to check reachability; no path costs more, so only the unreachable
+// tiles are left at f32::MAX.
+pub const MAX_PATH_DEPTH: f32 = NUM_TILES as f32 * TRAP_PATHING_COST * DIAGONAL_COST;
+
 pub fn map_idx(x: i32, y: i32) -> usize {
     ((y * SCREEN_WIDTH) + x) as usize
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Map {
     pub tiles: Vec<TileType>,
     pub revealed_tiles: Vec<bool>,
+    // Random value for each tile, used by the themes to vary the rendering (e.g. floor decorations).
+    pub variants: Vec<u8>,
+    // Light intensity of the tiles not reached by any light source (see LightMap).
+    pub ambient_light: f32,
+    // Tile indexes of the waypoints of the patrolling monsters.
+    pub patrol_points: Vec<usize>,
 }
 
 impl Map {
@@ -23,6 +70,9 @@ impl Map {
         Self {
             tiles: vec![TileType::Floor; NUM_TILES],
             revealed_tiles: vec![false; NUM_TILES],
+            variants: vec![0; NUM_TILES],
+            ambient_light: 1.0,
+            patrol_points: Vec::new(),
         }
     }
 
@@ -38,21 +88,39 @@ impl Map {
         }
     }
 
+    // Closed doors can't be entered directly; walking into them opens them (see the movement system).
+    //
     pub fn can_enter_tile(&self, point: Point) -> bool {
         self.in_bounds(point)
-            && (self.tiles[map_idx(point.x, point.y)] == TileType::Floor
-                || self.tiles[map_idx(point.x, point.y)] == TileType::Exit)
+            && matches!(
+                self.tiles[map_idx(point.x, point.y)],
+                TileType::Floor
+                    | TileType::Exit
+                    | TileType::StairsUp
+                    | TileType::OpenDoor
+                    | TileType::Trap
+                    | TileType::HiddenTrap
+                    | TileType::PressurePlate
+            )
+    }
+
+    // Pathing cost multiplier of entering the tile, if it's passable; locked doors are not, since only
+    // the player can open them (with a key).
+    //
+    pub fn pathing_cost(&self, idx: usize) -> Option<f32> {
+        match self.tiles[idx] {
+            TileType::Door => Some(DOOR_PATHING_COST),
+            TileType::Trap => Some(TRAP_PATHING_COST),
+            _ if self.can_enter_tile(self.index_to_point2d(idx)) => Some(1.0),
+            _ => None,
+        }
     }
 
-    fn valid_exit(&self, loc: Point, delta: Point) -> Option<usize> {
+    fn valid_exit(&self, loc: Point, delta: Point) -> Option<(usize, f32)> {
         let destination = loc + delta;
         if self.in_bounds(destination) {
-            if self.can_enter_tile(destination) {
-                let idx = self.point2d_to_index(destination);
-                Some(idx)
-            } else {
-                None
-            }
+            let idx = self.point2d_to_index(destination);
+            self.pathing_cost(idx).map(|cost| (idx, cost))
         } else {
             None
         }
@@ -71,24 +139,25 @@ impl Algorithm2D for Map {
 
 impl BaseMap for Map {
     fn is_opaque(&self, idx: usize) -> bool {
-        self.tiles[idx as usize] != TileType::Floor
+        matches!(
+            self.tiles[idx],
+            TileType::Wall | TileType::Door | TileType::LockedDoor | TileType::CrackedWall
+        )
     }
 
     fn get_available_exits(&self, idx: usize) -> SmallVec<[(usize, f32); 10]> {
         let mut exits = SmallVec::new();
         let location = self.index_to_point2d(idx);
 
-        if let Some(idx) = self.valid_exit(location, Point::new(-1, 0)) {
-            exits.push((idx, 1.0))
-        }
-        if let Some(idx) = self.valid_exit(location, Point::new(1, 0)) {
-            exits.push((idx, 1.0))
-        }
-        if let Some(idx) = self.valid_exit(location, Point::new(0, -1)) {
-            exits.push((idx, 1.0))
-        }
-        if let Some(idx) = self.valid_exit(location, Point::new(0, 1)) {
-            exits.push((idx, 1.0))
+        for delta in DIRECTIONS.iter() {
+            if let Some((idx, tile_cost)) = self.valid_exit(location, *delta) {
+                let cost = if delta.x != 0 && delta.y != 0 {
+                    DIAGONAL_COST
+                } else {
+                    1.0
+                };
+                exits.push((idx, cost * tile_cost))
+            }
         }
 
         exits
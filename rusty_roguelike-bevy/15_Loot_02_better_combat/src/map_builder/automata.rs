@@ -9,9 +9,12 @@ impl MapArchitect for CellularAutomataArchitect {
             map: Map::new(),
             rooms: Vec::new(),
             monster_spawns: Vec::new(),
+            item_spawns: Vec::new(),
+            key_spawns: Vec::new(),
+            torch_spawns: Vec::new(),
             player_start: Point::zero(),
             amulet_start: Point::zero(),
-            theme: super::themes::DungeonTheme::new(),
+            theme: Box::new(super::themes::Theme::default()),
         };
         self.random_noise_map(rng, &mut mb.map);
         for _ in 0..10 {
@@ -66,10 +69,11 @@ impl CellularAutomataArchitect {
         map.tiles = new_tiles;
     }
 
+    // If there are no floor tiles, the center is returned; this is caught by the validation.
+    //
     fn find_start(&self, map: &Map) -> Point {
         let center = Point::new(SCREEN_WIDTH / 2, SCREEN_HEIGHT / 2);
-        let closest_point = map
-            .tiles
+        map.tiles
             .iter()
             .enumerate()
             .filter(|(_, t)| **t == TileType::Floor)
@@ -80,8 +84,6 @@ impl CellularAutomataArchitect {
                 )
             })
             .min_by(|(_, distance), (_, distance2)| distance.partial_cmp(distance2).unwrap())
-            .map(|(idx, _)| idx)
-            .unwrap();
-        map.index_to_point2d(closest_point)
+            .map_or(center, |(idx, _)| map.index_to_point2d(idx))
     }
 }
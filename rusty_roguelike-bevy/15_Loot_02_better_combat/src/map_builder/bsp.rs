@@ -0,0 +1,116 @@
+use super::MapArchitect;
+use crate::prelude::*;
+use bracket_lib::prelude::Rect;
+
+const MIN_LEAF_SIZE: i32 = 8;
+const MAX_DEPTH: usize = 5;
+const MIN_ROOM_SIZE: i32 = 3;
+const MAX_ROOM_SIZE: i32 = 12;
+
+pub struct BspArchitect {}
+
+impl MapArchitect for BspArchitect {
+    fn new(&mut self, rng: &mut RandomNumberGenerator) -> MapBuilder {
+        let mut mb = MapBuilder {
+            map: Map::new(),
+            rooms: Vec::new(),
+            monster_spawns: Vec::new(),
+            item_spawns: Vec::new(),
+            key_spawns: Vec::new(),
+            torch_spawns: Vec::new(),
+            player_start: Point::zero(),
+            amulet_start: Point::zero(),
+            theme: Box::new(super::themes::Theme::default()),
+        };
+
+        mb.fill(TileType::Wall);
+        let area = Rect::with_exact(1, 1, SCREEN_WIDTH - 1, SCREEN_HEIGHT - 1);
+        self.subdivide(area, 0, rng, &mut mb);
+        let start = mb.rooms[0].center();
+        mb.monster_spawns = mb.spawn_monsters(&start, rng);
+        mb.player_start = start;
+        mb.amulet_start = mb.find_most_distant();
+        mb
+    }
+}
+
+impl BspArchitect {
+    // Splits the area in two, recursively, along the longer side; each leaf gets a room, and the two
+    // halves of each split are connected by a corridor. Returns the room used to connect the area to its
+    // sibling.
+    //
+    fn subdivide(
+        &mut self,
+        area: Rect,
+        depth: usize,
+        rng: &mut RandomNumberGenerator,
+        mb: &mut MapBuilder,
+    ) -> Rect {
+        let can_split_x = area.width() >= MIN_LEAF_SIZE * 2;
+        let can_split_y = area.height() >= MIN_LEAF_SIZE * 2;
+
+        if depth >= MAX_DEPTH || (!can_split_x && !can_split_y) {
+            return self.build_room(area, rng, mb);
+        }
+
+        let split_x = if can_split_x && can_split_y {
+            area.width() >= area.height()
+        } else {
+            can_split_x
+        };
+
+        let (first, second) = if split_x {
+            let x = rng.range(area.x1 + MIN_LEAF_SIZE, area.x2 - MIN_LEAF_SIZE + 1);
+            (
+                Rect::with_exact(area.x1, area.y1, x, area.y2),
+                Rect::with_exact(x, area.y1, area.x2, area.y2),
+            )
+        } else {
+            let y = rng.range(area.y1 + MIN_LEAF_SIZE, area.y2 - MIN_LEAF_SIZE + 1);
+            (
+                Rect::with_exact(area.x1, area.y1, area.x2, y),
+                Rect::with_exact(area.x1, y, area.x2, area.y2),
+            )
+        };
+
+        let first_room = self.subdivide(first, depth + 1, rng, mb);
+        let second_room = self.subdivide(second, depth + 1, rng, mb);
+
+        let (from, to) = (first_room.center(), second_room.center());
+        if rng.range(0, 2) == 1 {
+            mb.apply_horizontal_tunnel(from.x, to.x, from.y);
+            mb.apply_vertical_tunnel(from.y, to.y, to.x);
+        } else {
+            mb.apply_vertical_tunnel(from.y, to.y, from.x);
+            mb.apply_horizontal_tunnel(from.x, to.x, to.y);
+        }
+
+        if rng.range(0, 2) == 1 {
+            first_room
+        } else {
+            second_room
+        }
+    }
+
+    // The room is placed with a margin of one tile, so that the rooms of adjacent leaves don't merge.
+    //
+    fn build_room(
+        &mut self,
+        area: Rect,
+        rng: &mut RandomNumberGenerator,
+        mb: &mut MapBuilder,
+    ) -> Rect {
+        let width = rng.range(MIN_ROOM_SIZE, i32::min(area.width() - 1, MAX_ROOM_SIZE));
+        let height = rng.range(MIN_ROOM_SIZE, i32::min(area.height() - 1, MAX_ROOM_SIZE));
+        let x = rng.range(area.x1 + 1, area.x2 - width);
+        let y = rng.range(area.y1 + 1, area.y2 - height);
+
+        let room = Rect::with_size(x, y, width, height);
+        room.for_each(|p| {
+            mb.map.tiles[map_idx(p.x, p.y)] = TileType::Floor;
+        });
+        mb.rooms.push(room);
+
+        room
+    }
+}
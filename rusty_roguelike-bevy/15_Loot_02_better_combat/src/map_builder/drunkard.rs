@@ -13,9 +13,12 @@ impl MapArchitect for DrunkardsWalkArchitect {
             map: Map::new(),
             rooms: Vec::new(),
             monster_spawns: Vec::new(),
+            item_spawns: Vec::new(),
+            key_spawns: Vec::new(),
+            torch_spawns: Vec::new(),
             player_start: Point::zero(),
             amulet_start: Point::zero(),
-            theme: super::themes::DungeonTheme::new(),
+            theme: Box::new(super::themes::Theme::default()),
         };
 
         mb.fill(TileType::Wall);
@@ -0,0 +1,94 @@
+use crate::prelude::*;
+use ron::de::from_reader;
+use serde::Deserialize;
+use std::fs::File;
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct LevelConfig {
+    pub num_monsters: usize,
+    pub num_items: usize,
+    pub architects: Vec<ArchitectConfig>,
+    pub theme: String,
+    // Light intensity (0.0 to 1.0) of the areas not reached by any light source.
+    pub ambient_light: f32,
+    // Light sources placed by the architects, against the walls.
+    pub num_torches: usize,
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct ArchitectConfig {
+    pub architect: ArchitectType,
+    // Same semantics as the templates frequency.
+    pub frequency: i32,
+    #[serde(default)]
+    pub meta_builders: Vec<MetaBuilderType>,
+    // Overrides the level theme, for the maps generated by this architect.
+    #[serde(default)]
+    pub theme: Option<String>,
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct LevelsConfig {
+    // Number of levels of the dungeon; the Amulet of Yala is placed on the deepest one.
+    pub depth: u32,
+    pub levels: Vec<LevelConfig>,
+}
+
+impl LevelsConfig {
+    pub fn load() -> Self {
+        let file = File::open("resources/levels.ron").expect("Failed opening file");
+        let config: Self = from_reader(file).expect("Unable to load levels");
+
+        if config.depth == 0 || config.levels.is_empty() {
+            panic!("No levels configured");
+        }
+        for (i, level) in config.levels.iter().enumerate() {
+            if level
+                .architects
+                .iter()
+                .all(|architect| architect.frequency <= 0)
+            {
+                panic!("Level {} has no architects", i);
+            }
+        }
+
+        config
+    }
+
+    // The themes are loaded separately (see Themes), so they're checked at startup, rather than on each
+    // load.
+    //
+    pub fn check_themes(&self, themes: &Themes) {
+        for (i, level) in self.levels.iter().enumerate() {
+            let architect_themes = level
+                .architects
+                .iter()
+                .filter_map(|architect| architect.theme.as_ref());
+
+            for theme in std::iter::once(&level.theme).chain(architect_themes) {
+                if themes.theme(theme).is_none() {
+                    panic!("Level {} has an unknown theme: {}", i, theme);
+                }
+            }
+        }
+    }
+
+    // Levels deeper than the configured ones use the last configuration.
+    //
+    pub fn level(&self, level: usize) -> &LevelConfig {
+        &self.levels[level.min(self.levels.len() - 1)]
+    }
+}
+
+impl LevelConfig {
+    pub fn random_architect(&self, rng: &mut RandomNumberGenerator) -> &ArchitectConfig {
+        let mut available_architects = Vec::new();
+        for architect in self.architects.iter() {
+            for _ in 0..architect.frequency {
+                available_architects.push(architect);
+            }
+        }
+
+        rng.random_slice_entry(&available_architects).unwrap()
+    }
+}
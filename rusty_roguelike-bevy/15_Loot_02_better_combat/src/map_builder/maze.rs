@@ -0,0 +1,105 @@
+use super::MapArchitect;
+use crate::prelude::*;
+
+// The maze cells are on the odd coordinates; the tiles between them are the walls that can be removed.
+const CELLS_X: i32 = (SCREEN_WIDTH - 1) / 2;
+const CELLS_Y: i32 = (SCREEN_HEIGHT - 1) / 2;
+// Chance (percentage) of removing each of the remaining walls, so that the maze has loops; perfect mazes
+// make for frustrating play, since there's no way around monsters.
+const LOOP_CHANCE: i32 = 10;
+
+pub struct MazeArchitect {}
+
+impl MapArchitect for MazeArchitect {
+    fn new(&mut self, rng: &mut RandomNumberGenerator) -> MapBuilder {
+        let mut mb = MapBuilder {
+            map: Map::new(),
+            rooms: Vec::new(),
+            monster_spawns: Vec::new(),
+            item_spawns: Vec::new(),
+            key_spawns: Vec::new(),
+            torch_spawns: Vec::new(),
+            player_start: Point::zero(),
+            amulet_start: Point::zero(),
+            theme: Box::new(super::themes::Theme::default()),
+        };
+
+        mb.fill(TileType::Wall);
+        self.recursive_backtracker(rng, &mut mb.map);
+        self.add_loops(rng, &mut mb.map);
+        let start = cell_to_point(Point::new(CELLS_X / 2, CELLS_Y / 2));
+        mb.monster_spawns = mb.spawn_monsters(&start, rng);
+        mb.player_start = start;
+        mb.amulet_start = mb.find_most_distant();
+        mb
+    }
+}
+
+impl MazeArchitect {
+    // Iterative version; the recursive one may overflow the stack, since the path can be as long as the
+    // number of cells.
+    //
+    fn recursive_backtracker(&mut self, rng: &mut RandomNumberGenerator, map: &mut Map) {
+        let mut visited = vec![false; (CELLS_X * CELLS_Y) as usize];
+        let cell_idx = |cell: Point| (cell.y * CELLS_X + cell.x) as usize;
+
+        let start = Point::new(rng.range(0, CELLS_X), rng.range(0, CELLS_Y));
+        visited[cell_idx(start)] = true;
+        let start_idx = map.point2d_to_index(cell_to_point(start));
+        map.tiles[start_idx] = TileType::Floor;
+        let mut stack = vec![start];
+
+        while let Some(current) = stack.last().copied() {
+            let unvisited_neighbors = [
+                Point::new(-1, 0),
+                Point::new(1, 0),
+                Point::new(0, -1),
+                Point::new(0, 1),
+            ]
+            .iter()
+            .map(|delta| current + *delta)
+            .filter(|cell| {
+                cell.x >= 0
+                    && cell.x < CELLS_X
+                    && cell.y >= 0
+                    && cell.y < CELLS_Y
+                    && !visited[cell_idx(*cell)]
+            })
+            .collect::<Vec<_>>();
+
+            if let Some(next) = rng.random_slice_entry(&unvisited_neighbors) {
+                let (from, to) = (cell_to_point(current), cell_to_point(*next));
+                let wall = Point::new((from.x + to.x) / 2, (from.y + to.y) / 2);
+                let (wall_idx, to_idx) = (map.point2d_to_index(wall), map.point2d_to_index(to));
+                map.tiles[wall_idx] = TileType::Floor;
+                map.tiles[to_idx] = TileType::Floor;
+
+                visited[cell_idx(*next)] = true;
+                stack.push(*next);
+            } else {
+                stack.pop();
+            }
+        }
+    }
+
+    fn add_loops(&mut self, rng: &mut RandomNumberGenerator, map: &mut Map) {
+        for y in 1..CELLS_Y * 2 {
+            for x in 1..CELLS_X * 2 {
+                // Between two cells, either horizontally or vertically.
+                let is_cell_wall = x % 2 != y % 2;
+                let idx = map_idx(x, y);
+
+                if is_cell_wall
+                    && map.tiles[idx] == TileType::Wall
+                    && rng.range(0, 100) < LOOP_CHANCE
+                {
+                    map.tiles[idx] = TileType::Floor;
+                }
+            }
+        }
+    }
+}
+
+fn cell_to_point(cell: Point) -> Point {
+    Point::new(cell.x * 2 + 1, cell.y * 2 + 1)
+}
@@ -0,0 +1,194 @@
+use super::MapArchitect;
+use crate::prelude::*;
+use serde::Deserialize;
+
+// One in N doors placed by RoomDoors is locked.
+const LOCKED_DOOR_CHANCE: i32 = 6;
+const NUM_TRAPS: usize = 8;
+const NUM_PRESSURE_PLATES: usize = 2;
+// Traps are not placed too close to the player start, so that the player has a chance to look around.
+const MIN_TRAP_DISTANCE: f32 = 5.0;
+// Percentage of the eligible walls that are cracked.
+const CRACKED_WALL_CHANCE: i32 = 15;
+
+// Post-processing steps, applied to the map generated by an architect.
+//
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+pub enum MetaBuilderType {
+    // Places doors (some locked) on the narrow entrances of the rooms; it has no effect on architects
+    // without rooms.
+    RoomDoors,
+    // Removes the isolated wall tiles and the single-tile nooks.
+    SmoothCaves,
+    // Fills the areas not reachable from the player start.
+    CullUnreachable,
+    // Scatters hidden traps and pressure plates on the floor.
+    Traps,
+    // Cracks some of the thin walls between two open areas, creating shortcuts.
+    CrackedWalls,
+}
+
+// Combinator that runs the base architect, then the meta builders, in order.
+//
+pub struct LayeredArchitect {
+    pub(super) base: Box<dyn MapArchitect>,
+    pub(super) meta_builders: Vec<MetaBuilderType>,
+}
+
+impl MapArchitect for LayeredArchitect {
+    fn new(&mut self, rng: &mut RandomNumberGenerator) -> MapBuilder {
+        let mut mb = self.base.new(rng);
+
+        for meta_builder in self.meta_builders.iter() {
+            match meta_builder {
+                MetaBuilderType::RoomDoors => add_room_doors(&mut mb, rng),
+                MetaBuilderType::SmoothCaves => smooth_caves(&mut mb),
+                MetaBuilderType::CullUnreachable => cull_unreachable(&mut mb),
+                MetaBuilderType::Traps => add_traps(&mut mb, rng),
+                MetaBuilderType::CrackedWalls => crack_walls(&mut mb, rng),
+            }
+        }
+
+        // The layout may have changed, so the spawns and the amulet start are updated.
+        mb.monster_spawns
+            .retain(|pt| mb.map.tiles[mb.map.point2d_to_index(*pt)] == TileType::Floor);
+        mb.amulet_start = mb.find_most_distant();
+
+        mb
+    }
+}
+
+// A door is placed on the floor tiles just outside a room, when they're between two walls (so that it
+// can't be bypassed), and not next to another door. The keys for the locked doors are placed after the
+// prefabs (see MapBuilder::spawn_keys).
+//
+fn add_room_doors(mb: &mut MapBuilder, rng: &mut RandomNumberGenerator) {
+    for room in mb.rooms.iter() {
+        let mut perimeter = Vec::new();
+        for x in room.x1 - 1..=room.x2 {
+            perimeter.push(Point::new(x, room.y1 - 1));
+            perimeter.push(Point::new(x, room.y2));
+        }
+        for y in room.y1..room.y2 {
+            perimeter.push(Point::new(room.x1 - 1, y));
+            perimeter.push(Point::new(room.x2, y));
+        }
+
+        for pt in perimeter {
+            let is_tile = |pt: Point, tile_type: TileType| {
+                mb.map
+                    .try_idx(pt)
+                    .map_or(tile_type == TileType::Wall, |idx| {
+                        mb.map.tiles[idx] == tile_type
+                    })
+            };
+
+            let is_chokepoint = (is_tile(pt + Point::new(-1, 0), TileType::Wall)
+                && is_tile(pt + Point::new(1, 0), TileType::Wall))
+                || (is_tile(pt + Point::new(0, -1), TileType::Wall)
+                    && is_tile(pt + Point::new(0, 1), TileType::Wall));
+            let next_to_door = DIRECTIONS.iter().any(|delta| {
+                is_tile(pt + *delta, TileType::Door) || is_tile(pt + *delta, TileType::LockedDoor)
+            });
+
+            if is_tile(pt, TileType::Floor) && is_chokepoint && !next_to_door {
+                let idx = mb.map.point2d_to_index(pt);
+                mb.map.tiles[idx] = if rng.range(0, LOCKED_DOOR_CHANCE) == 0 {
+                    TileType::LockedDoor
+                } else {
+                    TileType::Door
+                };
+            }
+        }
+    }
+}
+
+fn smooth_caves(mb: &mut MapBuilder) {
+    let mut new_tiles = mb.map.tiles.clone();
+
+    for y in 1..SCREEN_HEIGHT - 1 {
+        for x in 1..SCREEN_WIDTH - 1 {
+            let pt = Point::new(x, y);
+            let walls = DIRECTIONS
+                .iter()
+                .filter(|delta| mb.map.tiles[map_idx(x + delta.x, y + delta.y)] == TileType::Wall)
+                .count();
+            let idx = map_idx(x, y);
+
+            match mb.map.tiles[idx] {
+                TileType::Wall if walls <= 2 => new_tiles[idx] = TileType::Floor,
+                TileType::Floor if walls >= 7 && pt != mb.player_start => {
+                    new_tiles[idx] = TileType::Wall
+                }
+                _ => {}
+            }
+        }
+    }
+
+    mb.map.tiles = new_tiles;
+}
+
+fn cull_unreachable(mb: &mut MapBuilder) {
+    let dijkstra_map = DijkstraMap::new(
+        SCREEN_WIDTH,
+        SCREEN_HEIGHT,
+        &[mb.map.point2d_to_index(mb.player_start)],
+        &mb.map,
+        MAX_PATH_DEPTH,
+    );
+
+    for (idx, distance) in dijkstra_map.map.iter().enumerate() {
+        if *distance == f32::MAX {
+            mb.map.tiles[idx] = TileType::Wall;
+        }
+    }
+}
+
+fn add_traps(mb: &mut MapBuilder, rng: &mut RandomNumberGenerator) {
+    let mut trappable_tiles: Vec<Point> = mb
+        .map
+        .tiles
+        .iter()
+        .enumerate()
+        .map(|(idx, t)| (mb.map.index_to_point2d(idx), t))
+        .filter(|(pt, t)| {
+            **t == TileType::Floor
+                && DistanceAlg::Pythagoras.distance2d(mb.player_start, *pt) > MIN_TRAP_DISTANCE
+                && !mb.monster_spawns.contains(pt)
+        })
+        .map(|(pt, _)| pt)
+        .collect();
+
+    for i in 0..NUM_TRAPS + NUM_PRESSURE_PLATES {
+        if let Some(target_index) = rng.random_slice_index(&trappable_tiles) {
+            let pt = trappable_tiles.remove(target_index);
+            let idx = mb.map.point2d_to_index(pt);
+            mb.map.tiles[idx] = if i < NUM_TRAPS {
+                TileType::HiddenTrap
+            } else {
+                TileType::PressurePlate
+            };
+        } else {
+            break;
+        }
+    }
+}
+
+// Only walls with floor on two opposite sides are cracked, so that breaking them always opens a passage.
+//
+fn crack_walls(mb: &mut MapBuilder, rng: &mut RandomNumberGenerator) {
+    for y in 1..SCREEN_HEIGHT - 1 {
+        for x in 1..SCREEN_WIDTH - 1 {
+            let is_floor = |x, y| mb.map.tiles[map_idx(x, y)] == TileType::Floor;
+            let idx = map_idx(x, y);
+
+            let is_thin_wall = mb.map.tiles[idx] == TileType::Wall
+                && ((is_floor(x - 1, y) && is_floor(x + 1, y))
+                    || (is_floor(x, y - 1) && is_floor(x, y + 1)));
+
+            if is_thin_wall && rng.range(0, 100) < CRACKED_WALL_CHANCE {
+                mb.map.tiles[idx] = TileType::CrackedWall;
+            }
+        }
+    }
+}
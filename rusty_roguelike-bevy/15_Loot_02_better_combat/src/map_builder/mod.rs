@@ -1,49 +1,182 @@
 use crate::prelude::*;
+use serde::Deserialize;
 mod empty;
+use empty::EmptyArchitect;
 mod rooms;
 use rooms::RoomsArchitect;
 mod automata;
 use automata::CellularAutomataArchitect;
 mod drunkard;
 use drunkard::DrunkardsWalkArchitect;
+mod bsp;
+use bsp::BspArchitect;
+mod maze;
+use maze::MazeArchitect;
+mod voronoi;
+use voronoi::VoronoiArchitect;
+mod meta_builders;
+use meta_builders::LayeredArchitect;
+pub use meta_builders::MetaBuilderType;
+mod level_config;
+pub use level_config::*;
 mod prefab;
-use prefab::apply_prefab;
+use prefab::apply_prefabs;
+pub use prefab::Prefabs;
 mod themes;
 pub use themes::*;
+mod validation;
 
 trait MapArchitect {
     fn new(&mut self, rng: &mut RandomNumberGenerator) -> MapBuilder;
 }
 
 pub trait MapTheme: Sync + Send {
-    fn tile_to_render(&self, tile_type: TileType) -> FontCharType;
+    // The variant is the random value assigned to the tile (see Map::variants).
+    fn tile_to_render(&self, tile_type: TileType, variant: u8) -> (FontCharType, ColorPair);
+    // Used to identify the theme in save files.
+    fn name(&self) -> &str;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+pub enum ArchitectType {
+    Empty,
+    Rooms,
+    CellularAutomata,
+    DrunkardsWalk,
+    Bsp,
+    Maze,
+    Voronoi,
+}
+
+impl ArchitectType {
+    // Used only by the map harness and the validation tests.
+    #[allow(dead_code)]
+    pub const ALL: [ArchitectType; 7] = [
+        ArchitectType::Empty,
+        ArchitectType::Rooms,
+        ArchitectType::CellularAutomata,
+        ArchitectType::DrunkardsWalk,
+        ArchitectType::Bsp,
+        ArchitectType::Maze,
+        ArchitectType::Voronoi,
+    ];
+
+    fn architect(&self) -> Box<dyn MapArchitect> {
+        match self {
+            ArchitectType::Empty => Box::new(EmptyArchitect {}),
+            ArchitectType::Rooms => Box::new(RoomsArchitect {}),
+            ArchitectType::CellularAutomata => Box::new(CellularAutomataArchitect {}),
+            ArchitectType::DrunkardsWalk => Box::new(DrunkardsWalkArchitect {}),
+            ArchitectType::Bsp => Box::new(BspArchitect {}),
+            ArchitectType::Maze => Box::new(MazeArchitect {}),
+            ArchitectType::Voronoi => Box::new(VoronoiArchitect {}),
+        }
+    }
 }
 
 const NUM_ROOMS: usize = 20;
+const MAX_GENERATION_ATTEMPTS: usize = 10;
+
 pub struct MapBuilder {
     pub map: Map,
     pub rooms: Vec<Rect>,
     pub monster_spawns: Vec<Point>,
+    pub item_spawns: Vec<Point>,
+    // One for each locked door.
+    pub key_spawns: Vec<Point>,
+    pub torch_spawns: Vec<Point>,
     pub player_start: Point,
     pub amulet_start: Point,
     pub theme: Box<dyn MapTheme>,
 }
 
 impl MapBuilder {
-    pub fn new(rng: &mut RandomNumberGenerator) -> Self {
-        let mut architect: Box<dyn MapArchitect> = match rng.range(0, 3) {
-            0 => Box::new(DrunkardsWalkArchitect {}),
-            1 => Box::new(RoomsArchitect {}),
-            _ => Box::new(CellularAutomataArchitect {}),
-        };
+    pub fn new(
+        rng: &mut RandomNumberGenerator,
+        level: usize,
+        levels_config: &LevelsConfig,
+        prefabs: &Prefabs,
+        themes: &Themes,
+    ) -> Self {
+        let level_config = levels_config.level(level);
+        let mut mb = None;
+
+        for _ in 0..MAX_GENERATION_ATTEMPTS {
+            let architect_config = level_config.random_architect(rng);
+            let candidate = Self::with_architect(
+                architect_config.architect,
+                &architect_config.meta_builders,
+                level,
+                level_config,
+                prefabs,
+                rng,
+            );
+
+            if candidate.validate(level_config).is_ok() {
+                let theme = architect_config
+                    .theme
+                    .as_ref()
+                    .unwrap_or(&level_config.theme);
+                mb = Some((candidate, theme));
+                break;
+            }
+        }
+
+        // The empty map is always valid (see the validation tests), so it's a safe (although dull)
+        // fallback.
+        let (mut mb, theme) = mb.unwrap_or_else(|| {
+            let fallback =
+                Self::with_architect(ArchitectType::Empty, &[], level, level_config, prefabs, rng);
+            if let Err(error) = fallback.validate(level_config) {
+                panic!("Invalid fallback map: {}", error);
+            }
+            (fallback, &level_config.theme)
+        });
+
+        mb.map.ambient_light = level_config.ambient_light;
+        mb.map.patrol_points = mb.patrol_points();
+        // The theme names are checked at startup (see LevelsConfig::check_themes).
+        mb.theme = themes.theme(theme).unwrap();
+        mb.map
+            .variants
+            .iter_mut()
+            .for_each(|variant| *variant = rng.range(0, 256) as u8);
+
+        mb
+    }
+
+    // Runs the given architect (followed by the meta builders, if any), sets the spawns according to the
+    // level configuration, and applies the prefabs; the theme is left to the caller.
+    //
+    pub fn with_architect(
+        architect_type: ArchitectType,
+        meta_builders: &[MetaBuilderType],
+        level: usize,
+        level_config: &LevelConfig,
+        prefabs: &Prefabs,
+        rng: &mut RandomNumberGenerator,
+    ) -> Self {
+        let mut architect = architect_type.architect();
+        if !meta_builders.is_empty() {
+            architect = Box::new(LayeredArchitect {
+                base: architect,
+                meta_builders: meta_builders.to_vec(),
+            });
+        }
         let mut mb = architect.new(rng);
-        apply_prefab(&mut mb, rng);
 
-        mb.theme = match rng.range(0, 2) {
-            0 => DungeonTheme::new(),
-            _ => ForestTheme::new(),
-        };
+        mb.item_spawns = mb.spawn_items(level_config.num_items, rng);
+        mb.torch_spawns = mb.spawn_torches(level_config.num_torches, rng);
+
+        apply_prefabs(&mut mb, prefabs, level, rng);
 
+        // Architects generate a fixed number of monster spawns, and the vaults add their own, so they're
+        // trimmed here.
+        while mb.monster_spawns.len() > level_config.num_monsters {
+            let idx = rng.random_slice_index(&mb.monster_spawns).unwrap();
+            mb.monster_spawns.remove(idx);
+        }
+        mb.key_spawns = mb.spawn_keys(rng);
         mb
     }
 
@@ -57,20 +190,18 @@ impl MapBuilder {
             SCREEN_HEIGHT,
             &[self.map.point2d_to_index(self.player_start)],
             &self.map,
-            1024.0,
+            MAX_PATH_DEPTH,
         );
 
+        // If nothing is reachable, the player start is returned; this is caught by the validation.
         const UNREACHABLE: &f32 = &f32::MAX;
-        self.map.index_to_point2d(
-            dijkstra_map
-                .map
-                .iter()
-                .enumerate()
-                .filter(|(_, dist)| *dist < UNREACHABLE)
-                .max_by(|a, b| a.1.partial_cmp(b.1).unwrap())
-                .unwrap()
-                .0,
-        )
+        dijkstra_map
+            .map
+            .iter()
+            .enumerate()
+            .filter(|(_, dist)| *dist < UNREACHABLE)
+            .max_by(|a, b| a.1.partial_cmp(b.1).unwrap())
+            .map_or(self.player_start, |(idx, _)| self.map.index_to_point2d(idx))
     }
 
     fn build_random_rooms(&mut self, rng: &mut RandomNumberGenerator) {
@@ -136,6 +267,148 @@ impl MapBuilder {
         }
     }
 
+    fn spawn_items(&self, num_items: usize, rng: &mut RandomNumberGenerator) -> Vec<Point> {
+        let mut spawnable_tiles: Vec<Point> = self
+            .map
+            .tiles
+            .iter()
+            .enumerate()
+            .map(|(idx, t)| (self.map.index_to_point2d(idx), t))
+            .filter(|(pt, t)| {
+                **t == TileType::Floor
+                    && *pt != self.player_start
+                    && !self.monster_spawns.contains(pt)
+            })
+            .map(|(pt, _)| pt)
+            .collect();
+
+        let mut spawns = Vec::new();
+        for _ in 0..num_items {
+            if let Some(target_index) = rng.random_slice_index(&spawnable_tiles) {
+                spawns.push(spawnable_tiles[target_index]);
+                spawnable_tiles.remove(target_index);
+            } else {
+                break;
+            }
+        }
+        spawns
+    }
+
+    // Torches stand against the walls; when there are rooms, they're lit first (one torch each), so that
+    // the corridors are the darkest areas.
+    //
+    fn spawn_torches(&self, num_torches: usize, rng: &mut RandomNumberGenerator) -> Vec<Point> {
+        let against_wall = |pt: Point| {
+            self.map.tiles[self.map.point2d_to_index(pt)] == TileType::Floor
+                && pt != self.player_start
+                && !self.item_spawns.contains(&pt)
+                && DIRECTIONS.iter().any(|delta| {
+                    self.map
+                        .try_idx(pt + *delta)
+                        .is_some_and(|idx| self.map.tiles[idx] == TileType::Wall)
+                })
+        };
+
+        let mut spawns = Vec::new();
+        for room in self.rooms.iter() {
+            let mut room_tiles = Vec::new();
+            room.for_each(|pt| {
+                if self.map.in_bounds(pt) && against_wall(pt) {
+                    room_tiles.push(pt);
+                }
+            });
+            if let Some(pt) = rng.random_slice_entry(&room_tiles) {
+                if spawns.len() < num_torches {
+                    spawns.push(*pt);
+                }
+            }
+        }
+
+        let mut spawnable_tiles: Vec<Point> = (0..self.map.tiles.len())
+            .map(|idx| self.map.index_to_point2d(idx))
+            .filter(|pt| against_wall(*pt) && !spawns.contains(pt))
+            .collect();
+
+        while spawns.len() < num_torches {
+            if let Some(target_index) = rng.random_slice_index(&spawnable_tiles) {
+                spawns.push(spawnable_tiles.remove(target_index));
+            } else {
+                break;
+            }
+        }
+        spawns
+    }
+
+    // The keys are placed where they can be reached without opening any locked door; since any key opens
+    // any door, this guarantees that all the locked doors can be opened.
+    //
+    fn spawn_keys(&self, rng: &mut RandomNumberGenerator) -> Vec<Point> {
+        let num_keys = self
+            .map
+            .tiles
+            .iter()
+            .filter(|t| **t == TileType::LockedDoor)
+            .count();
+
+        let dijkstra_map = DijkstraMap::new(
+            SCREEN_WIDTH,
+            SCREEN_HEIGHT,
+            &[self.map.point2d_to_index(self.player_start)],
+            &self.map,
+            MAX_PATH_DEPTH,
+        );
+
+        let mut spawnable_tiles: Vec<Point> = self
+            .map
+            .tiles
+            .iter()
+            .enumerate()
+            .filter(|(idx, t)| **t == TileType::Floor && dijkstra_map.map[*idx] < f32::MAX)
+            .map(|(idx, _)| self.map.index_to_point2d(idx))
+            .filter(|pt| {
+                *pt != self.player_start
+                    && *pt != self.amulet_start
+                    && !self.item_spawns.contains(pt)
+                    && !self.monster_spawns.contains(pt)
+                    && !self.torch_spawns.contains(pt)
+            })
+            .collect();
+
+        // Missing keys are caught by the validation.
+        let mut spawns = Vec::new();
+        for _ in 0..num_keys {
+            if let Some(target_index) = rng.random_slice_index(&spawnable_tiles) {
+                spawns.push(spawnable_tiles.remove(target_index));
+            } else {
+                break;
+            }
+        }
+        spawns
+    }
+
+    // The monsters patrol between the rooms; the maps without rooms use the monster spawns, which are
+    // spread over the map.
+    //
+    fn patrol_points(&self) -> Vec<usize> {
+        let room_centers = self
+            .rooms
+            .iter()
+            .map(|room| room.center())
+            .filter(|pt| self.map.can_enter_tile(*pt))
+            .collect::<Vec<_>>();
+
+        let points = if room_centers.is_empty() {
+            &self.monster_spawns
+        } else {
+            &room_centers
+        };
+
+        points
+            .iter()
+            .map(|pt| self.map.point2d_to_index(*pt))
+            .collect()
+    }
+
     fn spawn_monsters(&self, start: &Point, rng: &mut RandomNumberGenerator) -> Vec<Point> {
         const NUM_MONSTERS: usize = 50;
         let mut spawnable_tiles: Vec<Point> = self
@@ -151,11 +424,15 @@ impl MapBuilder {
             .map(|(idx, _)| self.map.index_to_point2d(idx))
             .collect();
 
+        // Small maps may have less spawnable tiles than monsters; the validation decides if they're enough.
         let mut spawns = Vec::new();
         for _ in 0..NUM_MONSTERS {
-            let target_index = rng.random_slice_index(&spawnable_tiles).unwrap();
-            spawns.push(spawnable_tiles[target_index]);
-            spawnable_tiles.remove(target_index);
+            if let Some(target_index) = rng.random_slice_index(&spawnable_tiles) {
+                spawns.push(spawnable_tiles[target_index]);
+                spawnable_tiles.remove(target_index);
+            } else {
+                break;
+            }
         }
         spawns
     }
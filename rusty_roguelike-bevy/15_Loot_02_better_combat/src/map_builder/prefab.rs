@@ -1,81 +1,211 @@
 use crate::prelude::*;
+use bracket_lib::prelude::Rect;
+use ron::de::from_reader;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
 
-const FORTRESS: (&str, i32, i32) = (
-    "
-------------
----######---
----#----#---
----#-M--#---
--###----###-
---M------M--
--###----###-
----#----#---
----#----#---
----######---
-------------
-",
-    12,
-    11,
-);
-
-pub fn apply_prefab(mb: &mut MapBuilder, rng: &mut RandomNumberGenerator) {
-    let mut placement = None;
+#[derive(Clone, Copy, Deserialize, Debug, PartialEq)]
+pub enum PrefabTile {
+    Wall,
+    Floor,
+    MonsterSpawn,
+    ItemSpawn,
+    Exit,
+    Door,
+    // The key is placed outside the vault (see MapBuilder::spawn_keys).
+    LockedDoor,
+    CrackedWall,
+    // Hidden.
+    Trap,
+    PressurePlate,
+    // On the floor.
+    Torch,
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct Vault {
+    pub name: String,
+    pub levels: HashSet<usize>,
+    pub frequency: i32,
+    pub legend: HashMap<char, PrefabTile>,
+    pub map: Vec<String>,
+    #[serde(default)]
+    pub rotate: bool,
+    #[serde(default)]
+    pub mirror: bool,
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct Prefabs {
+    pub vaults_per_level: usize,
+    pub vaults: Vec<Vault>,
+}
+
+impl Prefabs {
+    pub fn load() -> Self {
+        let file = File::open("resources/prefabs.ron").expect("Failed opening file");
+        let prefabs: Self = from_reader(file).expect("Unable to load prefabs");
+
+        // Errors are detected on load, rather than when (and if) a given vault is placed.
+        for vault in &prefabs.vaults {
+            let width = vault.map.first().map_or(0, |line| line.chars().count());
+            let height = vault.map.len();
+            // Rotated vaults may swap their width and height.
+            let (max_width, max_height) = if vault.rotate {
+                let side = width.max(height);
+                (side, side)
+            } else {
+                (width, height)
+            };
+
+            if width == 0
+                || max_width >= SCREEN_WIDTH as usize
+                || max_height >= SCREEN_HEIGHT as usize
+            {
+                panic!("Vault {} has invalid dimensions", vault.name);
+            }
+            for line in &vault.map {
+                if line.chars().count() != width {
+                    panic!("Vault {} has lines of different length", vault.name);
+                }
+                if let Some(c) = line.chars().find(|c| !vault.legend.contains_key(c)) {
+                    panic!(
+                        "Vault {} has a character not in the legend: [{}]",
+                        vault.name, c
+                    );
+                }
+            }
+        }
+
+        prefabs
+    }
+}
+
+impl Vault {
+    // Returns the vault tiles (as rows), randomly rotated/mirrored, if allowed.
+    //
+    fn transformed_tiles(&self, rng: &mut RandomNumberGenerator) -> Vec<Vec<PrefabTile>> {
+        let mut rows = self
+            .map
+            .iter()
+            .map(|line| line.chars().map(|c| self.legend[&c]).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        if self.mirror && rng.range(0, 2) == 1 {
+            rows.iter_mut().for_each(|row| row.reverse());
+        }
+
+        if self.rotate {
+            for _ in 0..rng.range(0, 4) {
+                rows = rotate_clockwise(&rows);
+            }
+        }
+
+        rows
+    }
+}
+
+fn rotate_clockwise(rows: &[Vec<PrefabTile>]) -> Vec<Vec<PrefabTile>> {
+    let height = rows.len();
+    let width = rows[0].len();
+
+    (0..width)
+        .map(|x| (0..height).rev().map(|y| rows[y][x]).collect())
+        .collect()
+}
+
+pub fn apply_prefabs(
+    mb: &mut MapBuilder,
+    prefabs: &Prefabs,
+    level: usize,
+    rng: &mut RandomNumberGenerator,
+) {
+    let mut available_vaults = Vec::new();
+    for vault in prefabs.vaults.iter() {
+        if vault.levels.contains(&level) {
+            for _ in 0..vault.frequency {
+                available_vaults.push(vault);
+            }
+        }
+    }
 
     let dijkstra_map = DijkstraMap::new(
         SCREEN_WIDTH,
         SCREEN_HEIGHT,
         &[mb.map.point2d_to_index(mb.player_start)],
         &mb.map,
-        1024.0,
+        MAX_PATH_DEPTH,
     );
 
-    let mut attempts = 0;
-    while placement.is_none() && attempts < 10 {
-        let dimensions = Rect::with_size(
-            rng.range(0, SCREEN_WIDTH - FORTRESS.1),
-            rng.range(0, SCREEN_HEIGHT - FORTRESS.2),
-            FORTRESS.1,
-            FORTRESS.2,
-        );
-
-        let mut can_place = false;
-        dimensions.for_each(|pt| {
-            let idx = mb.map.point2d_to_index(pt);
-            let distance = dijkstra_map.map[idx];
-            if distance < 2000.0 && distance > 20.0 && mb.amulet_start != pt {
-                can_place = true;
-            }
-        });
+    let mut placed_areas = Vec::<Rect>::new();
+
+    for _ in 0..prefabs.vaults_per_level {
+        let vault = if let Some(vault) = rng.random_slice_entry(&available_vaults) {
+            vault
+        } else {
+            return;
+        };
+        let tiles = vault.transformed_tiles(rng);
+        let (width, height) = (tiles[0].len() as i32, tiles.len() as i32);
+
+        let mut placement = None;
+        let mut attempts = 0;
+        while placement.is_none() && attempts < 10 {
+            let dimensions = Rect::with_size(
+                rng.range(0, SCREEN_WIDTH - width),
+                rng.range(0, SCREEN_HEIGHT - height),
+                width,
+                height,
+            );
 
-        if can_place {
-            placement = Some(Point::new(dimensions.x1, dimensions.y1));
-            let points = dimensions.point_set();
-            mb.monster_spawns.retain(|pt| !points.contains(pt));
+            // Placing a vault over the player or the amulet would wall them.
+            let mut can_place = false;
+            let covers_start = dimensions.point_in_rect(mb.player_start)
+                || dimensions.point_in_rect(mb.amulet_start);
+            let overlaps = placed_areas.iter().any(|area| area.intersect(&dimensions));
+            dimensions.for_each(|pt| {
+                let idx = mb.map.point2d_to_index(pt);
+                let distance = dijkstra_map.map[idx];
+                if distance < 2000.0 && distance > 20.0 && mb.amulet_start != pt {
+                    can_place = true;
+                }
+            });
+
+            if can_place && !covers_start && !overlaps {
+                placement = Some(Point::new(dimensions.x1, dimensions.y1));
+                let points = dimensions.point_set();
+                mb.monster_spawns.retain(|pt| !points.contains(pt));
+                mb.item_spawns.retain(|pt| !points.contains(pt));
+                mb.torch_spawns.retain(|pt| !points.contains(pt));
+                placed_areas.push(dimensions);
+            }
+            attempts += 1;
         }
-        attempts += 1;
-    }
 
-    if let Some(placement) = placement {
-        let string_vec: Vec<char> = FORTRESS
-            .0
-            .chars()
-            .filter(|a| *a != '\r' && *a != '\n')
-            .collect();
-        let mut i = 0;
-        for ty in placement.y..placement.y + FORTRESS.2 {
-            for tx in placement.x..placement.x + FORTRESS.1 {
-                let idx = map_idx(tx, ty);
-                let c = string_vec[i];
-                match c {
-                    'M' => {
-                        mb.map.tiles[idx] = TileType::Floor;
-                        mb.monster_spawns.push(Point::new(tx, ty));
+        if let Some(placement) = placement {
+            for (y, row) in tiles.iter().enumerate() {
+                for (x, tile) in row.iter().enumerate() {
+                    let pt = placement + Point::new(x as i32, y as i32);
+                    let idx = map_idx(pt.x, pt.y);
+                    mb.map.tiles[idx] = match tile {
+                        PrefabTile::Wall => TileType::Wall,
+                        PrefabTile::Door => TileType::Door,
+                        PrefabTile::LockedDoor => TileType::LockedDoor,
+                        PrefabTile::CrackedWall => TileType::CrackedWall,
+                        PrefabTile::Trap => TileType::HiddenTrap,
+                        PrefabTile::PressurePlate => TileType::PressurePlate,
+                        _ => TileType::Floor,
+                    };
+                    match tile {
+                        PrefabTile::MonsterSpawn => mb.monster_spawns.push(pt),
+                        PrefabTile::ItemSpawn => mb.item_spawns.push(pt),
+                        PrefabTile::Torch => mb.torch_spawns.push(pt),
+                        // The exit tile is set on the amulet start, when the level is built.
+                        PrefabTile::Exit => mb.amulet_start = pt,
+                        _ => {}
                     }
-                    '-' => mb.map.tiles[idx] = TileType::Floor,
-                    '#' => mb.map.tiles[idx] = TileType::Wall,
-                    _ => println!("No idea what to do with [{}]", c),
                 }
-                i += 1;
             }
         }
     }
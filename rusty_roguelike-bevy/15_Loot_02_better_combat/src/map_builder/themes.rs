@@ -1,37 +1,92 @@
 use crate::prelude::*;
+use ron::de::from_reader;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs::File;
 
-pub struct DungeonTheme {}
+#[derive(Clone, Copy, Deserialize, Debug)]
+pub struct TileStyle {
+    pub glyph: char,
+    pub fg: (u8, u8, u8),
+    pub bg: (u8, u8, u8),
+}
 
-impl DungeonTheme {
-    pub fn new() -> Box<dyn MapTheme> {
-        Box::new(Self {})
-    }
+// Themes are loaded from data. The default theme (used as placeholder by the architects) renders all the
+// tiles with their basic glyph, in white.
+//
+#[derive(Clone, Deserialize, Debug, Default)]
+pub struct Theme {
+    pub name: String,
+    pub tiles: HashMap<TileType, TileStyle>,
+    // Alternative renderings of the floor tiles.
+    #[serde(default)]
+    pub decorations: Vec<TileStyle>,
+    // Percentage of the floor tiles that are decorated.
+    #[serde(default)]
+    pub decoration_chance: i32,
 }
 
-impl MapTheme for DungeonTheme {
-    fn tile_to_render(&self, tile_type: TileType) -> FontCharType {
-        match tile_type {
-            TileType::Floor => to_cp437('.'),
-            TileType::Wall => to_cp437('#'),
-            TileType::Exit => to_cp437('>'),
+impl MapTheme for Theme {
+    fn tile_to_render(&self, tile_type: TileType, variant: u8) -> (FontCharType, ColorPair) {
+        // Hidden traps must be indistinguishable from the floor.
+        let tile_type = match tile_type {
+            TileType::HiddenTrap => TileType::Floor,
+            other => other,
+        };
+
+        let decorated = tile_type == TileType::Floor
+            && !self.decorations.is_empty()
+            && (variant as i32 * 100 / 256) < self.decoration_chance;
+
+        let style = if decorated {
+            Some(&self.decorations[variant as usize % self.decorations.len()])
+        } else {
+            self.tiles.get(&tile_type)
+        };
+
+        match style {
+            Some(style) => (to_cp437(style.glyph), ColorPair::new(style.fg, style.bg)),
+            None => {
+                let glyph = match tile_type {
+                    TileType::Floor => '.',
+                    TileType::Wall => '#',
+                    TileType::Exit => '>',
+                    TileType::StairsUp => '<',
+                    TileType::Door | TileType::LockedDoor => '+',
+                    TileType::OpenDoor => '\'',
+                    TileType::CrackedWall => '%',
+                    TileType::Trap => '^',
+                    TileType::PressurePlate => '_',
+                    TileType::HiddenTrap => unreachable!(),
+                };
+                (to_cp437(glyph), ColorPair::new(WHITE, BLACK))
+            }
         }
     }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
 }
 
-pub struct ForestTheme {}
+// Loaded once, and stored as resource; the themes are looked up by name, since that's what the levels
+// configuration and the saves refer to.
+//
+#[derive(Deserialize, Debug)]
+pub struct Themes {
+    pub themes: Vec<Theme>,
+}
 
-impl MapTheme for ForestTheme {
-    fn tile_to_render(&self, tile_type: TileType) -> FontCharType {
-        match tile_type {
-            TileType::Floor => to_cp437(';'),
-            TileType::Wall => to_cp437('"'),
-            TileType::Exit => to_cp437('>'),
-        }
+impl Themes {
+    pub fn load() -> Self {
+        let file = File::open("resources/themes.ron").expect("Failed opening file");
+        from_reader(file).expect("Unable to load themes")
     }
-}
 
-impl ForestTheme {
-    pub fn new() -> Box<dyn MapTheme> {
-        Box::new(Self {})
+    pub fn theme(&self, name: &str) -> Option<Box<dyn MapTheme>> {
+        self.themes
+            .iter()
+            .find(|theme| theme.name == name)
+            .map(|theme| Box::new(theme.clone()) as Box<dyn MapTheme>)
     }
 }
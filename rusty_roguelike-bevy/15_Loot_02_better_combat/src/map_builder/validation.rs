@@ -0,0 +1,266 @@
+use crate::prelude::*;
+use std::fmt;
+
+// Some monster spawns may be lost (e.g. under the prefabs), and the rooms architect spawns one monster per
+// room, but a map must keep at least this share of the configured monsters.
+const MIN_MONSTER_SPAWNS_RATIO: f32 = 0.4;
+
+#[derive(Debug)]
+pub enum MapValidationError {
+    PlayerStartBlocked,
+    AmuletAtPlayerStart,
+    AmuletUnreachable,
+    NotEnoughMonsterSpawns { found: usize, minimum: usize },
+    MonsterSpawnBlocked(Point),
+    MissingKeys(usize),
+}
+
+impl fmt::Display for MapValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MapValidationError::PlayerStartBlocked => {
+                write!(f, "the player start is not enterable")
+            }
+            MapValidationError::AmuletAtPlayerStart => {
+                write!(f, "the amulet start is the same as the player start")
+            }
+            MapValidationError::AmuletUnreachable => {
+                write!(f, "the amulet start is not reachable from the player start")
+            }
+            MapValidationError::NotEnoughMonsterSpawns { found, minimum } => write!(
+                f,
+                "not enough monster spawns ({}, minimum {})",
+                found, minimum
+            ),
+            MapValidationError::MonsterSpawnBlocked(pt) => {
+                write!(f, "the monster spawn at {:?} is not enterable", pt)
+            }
+            MapValidationError::MissingKeys(count) => {
+                write!(f, "{} locked doors have no key", count)
+            }
+        }
+    }
+}
+
+impl MapBuilder {
+    // Checks that the map is playable, with the given level configuration. The amulet start is also the
+    // exit location, so checking it covers both cases.
+    //
+    pub fn validate(&self, level_config: &LevelConfig) -> Result<(), MapValidationError> {
+        if !self.map.can_enter_tile(self.player_start) {
+            return Err(MapValidationError::PlayerStartBlocked);
+        }
+
+        if self.amulet_start == self.player_start {
+            return Err(MapValidationError::AmuletAtPlayerStart);
+        }
+
+        let locked_doors = self
+            .map
+            .tiles
+            .iter()
+            .filter(|t| **t == TileType::LockedDoor)
+            .count();
+        if self.key_spawns.len() < locked_doors {
+            return Err(MapValidationError::MissingKeys(
+                locked_doors - self.key_spawns.len(),
+            ));
+        }
+
+        // All the keys are reachable (see MapBuilder::spawn_keys), so the locked doors are passable.
+        //
+        let mut unlocked_map = self.map.clone();
+        unlocked_map
+            .tiles
+            .iter_mut()
+            .filter(|t| **t == TileType::LockedDoor)
+            .for_each(|t| *t = TileType::Door);
+
+        let dijkstra_map = DijkstraMap::new(
+            SCREEN_WIDTH,
+            SCREEN_HEIGHT,
+            &[self.map.point2d_to_index(self.player_start)],
+            &unlocked_map,
+            MAX_PATH_DEPTH,
+        );
+
+        if !self.map.in_bounds(self.amulet_start)
+            || dijkstra_map.map[self.map.point2d_to_index(self.amulet_start)] == f32::MAX
+        {
+            return Err(MapValidationError::AmuletUnreachable);
+        }
+
+        let min_monster_spawns =
+            (level_config.num_monsters as f32 * MIN_MONSTER_SPAWNS_RATIO).ceil() as usize;
+        if self.monster_spawns.len() < min_monster_spawns {
+            return Err(MapValidationError::NotEnoughMonsterSpawns {
+                found: self.monster_spawns.len(),
+                minimum: min_monster_spawns,
+            });
+        }
+
+        // Monsters spawned in unreachable areas are harmless, so only enterability is checked.
+        if let Some(pt) = self
+            .monster_spawns
+            .iter()
+            .find(|pt| !self.map.can_enter_tile(**pt))
+        {
+            return Err(MapValidationError::MonsterSpawnBlocked(*pt));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    // Per architect.
+    const NUM_SEEDS: u64 = 2000;
+    // Per configured architect (with its meta builders); the architects alone are tested more thoroughly.
+    const NUM_CONFIGURED_SEEDS: u64 = 1000;
+    // Per level; each level generation may run several architects.
+    const NUM_LEVEL_SEEDS: u64 = 500;
+    // Invalid maps are regenerated (see MapBuilder::new), so an architect may produce a few, as long as
+    // running out of attempts is unlikely.
+    const MAX_INVALID_RATIO: f64 = 0.02;
+
+    fn count_invalid(
+        architect_type: ArchitectType,
+        meta_builders: &[MetaBuilderType],
+        level: usize,
+        levels_config: &LevelsConfig,
+        prefabs: &Prefabs,
+        num_seeds: u64,
+    ) -> usize {
+        (0..num_seeds)
+            .filter(|seed| {
+                let mut rng = RandomNumberGenerator::seeded(*seed);
+                let mb = MapBuilder::with_architect(
+                    architect_type,
+                    meta_builders,
+                    level,
+                    levels_config.level(level),
+                    prefabs,
+                    &mut rng,
+                );
+                mb.validate(levels_config.level(level)).is_err()
+            })
+            .count()
+    }
+
+    #[test]
+    fn architects_generate_mostly_valid_maps() {
+        let levels_config = LevelsConfig::load();
+        let prefabs = Prefabs::load();
+
+        for architect_type in ArchitectType::ALL {
+            let invalid =
+                count_invalid(architect_type, &[], 0, &levels_config, &prefabs, NUM_SEEDS);
+
+            assert!(
+                invalid as f64 <= NUM_SEEDS as f64 * MAX_INVALID_RATIO,
+                "{:?}: {} invalid maps out of {}",
+                architect_type,
+                invalid,
+                NUM_SEEDS
+            );
+        }
+    }
+
+    #[test]
+    fn configured_architects_generate_mostly_valid_maps() {
+        let levels_config = LevelsConfig::load();
+        let prefabs = Prefabs::load();
+
+        for (level, level_config) in levels_config.levels.iter().enumerate() {
+            for architect_config in level_config.architects.iter() {
+                let invalid = count_invalid(
+                    architect_config.architect,
+                    &architect_config.meta_builders,
+                    level,
+                    &levels_config,
+                    &prefabs,
+                    NUM_CONFIGURED_SEEDS,
+                );
+
+                assert!(
+                    invalid as f64 <= NUM_CONFIGURED_SEEDS as f64 * MAX_INVALID_RATIO,
+                    "level {}, {:?} {:?}: {} invalid maps out of {}",
+                    level,
+                    architect_config.architect,
+                    architect_config.meta_builders,
+                    invalid,
+                    NUM_CONFIGURED_SEEDS
+                );
+            }
+        }
+    }
+
+    // MapBuilder::new relies on it.
+    //
+    #[test]
+    fn fallback_maps_are_valid() {
+        let levels_config = LevelsConfig::load();
+        let prefabs = Prefabs::load();
+
+        for level in 0..levels_config.levels.len() {
+            let invalid = count_invalid(
+                ArchitectType::Empty,
+                &[],
+                level,
+                &levels_config,
+                &prefabs,
+                NUM_SEEDS,
+            );
+
+            assert_eq!(invalid, 0, "level {}", level);
+        }
+    }
+
+    // The minimum number of monster spawns follows the configuration.
+    //
+    #[test]
+    fn levels_with_few_monsters_are_valid() {
+        let mut level_config = LevelsConfig::load().level(0).clone();
+        level_config.num_monsters = 3;
+        let prefabs = Prefabs::load();
+
+        for seed in 0..NUM_LEVEL_SEEDS {
+            let mut rng = RandomNumberGenerator::seeded(seed);
+            let mb = MapBuilder::with_architect(
+                ArchitectType::Empty,
+                &[],
+                0,
+                &level_config,
+                &prefabs,
+                &mut rng,
+            );
+
+            assert!(mb.monster_spawns.len() <= 3);
+            if let Err(error) = mb.validate(&level_config) {
+                panic!("seed {}: {}", seed, error);
+            }
+        }
+    }
+
+    #[test]
+    fn generated_levels_are_valid() {
+        let levels_config = LevelsConfig::load();
+        let prefabs = Prefabs::load();
+        let themes = Themes::load();
+        levels_config.check_themes(&themes);
+
+        for level in 0..levels_config.depth as usize {
+            for seed in 0..NUM_LEVEL_SEEDS {
+                let mut rng = RandomNumberGenerator::seeded(seed);
+                let mb = MapBuilder::new(&mut rng, level, &levels_config, &prefabs, &themes);
+
+                if let Err(error) = mb.validate(levels_config.level(level)) {
+                    panic!("level {}, seed {}: {}", level, seed, error);
+                }
+            }
+        }
+    }
+}
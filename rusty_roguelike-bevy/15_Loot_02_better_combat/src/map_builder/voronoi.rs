@@ -0,0 +1,95 @@
+use super::MapArchitect;
+use crate::prelude::*;
+
+const NUM_SEEDS: usize = 48;
+// Chance (percentage) of each region being open.
+const OPEN_REGION_CHANCE: i32 = 60;
+// Tiles (almost) equidistant from two seeds are walls, so that the regions are separated.
+const BORDER_THICKNESS: f32 = 1.0;
+
+pub struct VoronoiArchitect {}
+
+impl MapArchitect for VoronoiArchitect {
+    fn new(&mut self, rng: &mut RandomNumberGenerator) -> MapBuilder {
+        let mut mb = MapBuilder {
+            map: Map::new(),
+            rooms: Vec::new(),
+            monster_spawns: Vec::new(),
+            item_spawns: Vec::new(),
+            key_spawns: Vec::new(),
+            torch_spawns: Vec::new(),
+            player_start: Point::zero(),
+            amulet_start: Point::zero(),
+            theme: Box::new(super::themes::Theme::default()),
+        };
+
+        mb.fill(TileType::Wall);
+
+        let seeds = (0..NUM_SEEDS)
+            .map(|_| {
+                let seed = Point::new(
+                    rng.range(1, SCREEN_WIDTH - 1),
+                    rng.range(1, SCREEN_HEIGHT - 1),
+                );
+                let open = rng.range(0, 100) < OPEN_REGION_CHANCE;
+                (seed, open)
+            })
+            .collect::<Vec<_>>();
+
+        self.carve_regions(&seeds, &mut mb.map);
+
+        // The open regions are connected in the same way as the rooms of the rooms architect; since the
+        // corridors go through the seeds, these are all enterable.
+        //
+        let mut open_seeds = seeds
+            .iter()
+            .filter(|(_, open)| *open)
+            .map(|(seed, _)| *seed)
+            .collect::<Vec<_>>();
+        if open_seeds.is_empty() {
+            open_seeds.push(seeds[0].0);
+        }
+        open_seeds.sort_by_key(|seed| seed.x);
+
+        for (i, seed) in open_seeds.iter().enumerate().skip(1) {
+            let prev = open_seeds[i - 1];
+
+            if rng.range(0, 2) == 1 {
+                mb.apply_horizontal_tunnel(prev.x, seed.x, prev.y);
+                mb.apply_vertical_tunnel(prev.y, seed.y, seed.x);
+            } else {
+                mb.apply_vertical_tunnel(prev.y, seed.y, prev.x);
+                mb.apply_horizontal_tunnel(prev.x, seed.x, seed.y);
+            }
+        }
+
+        let start = open_seeds[open_seeds.len() / 2];
+        mb.monster_spawns = mb.spawn_monsters(&start, rng);
+        mb.player_start = start;
+        mb.amulet_start = mb.find_most_distant();
+        mb
+    }
+}
+
+impl VoronoiArchitect {
+    fn carve_regions(&mut self, seeds: &[(Point, bool)], map: &mut Map) {
+        for y in 1..SCREEN_HEIGHT - 1 {
+            for x in 1..SCREEN_WIDTH - 1 {
+                let pt = Point::new(x, y);
+
+                let mut distances = seeds
+                    .iter()
+                    .map(|(seed, open)| (DistanceAlg::Pythagoras.distance2d(pt, *seed), *open))
+                    .collect::<Vec<_>>();
+                distances.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+                let (nearest_distance, open) = distances[0];
+                let second_distance = distances[1].0;
+
+                if open && second_distance - nearest_distance >= BORDER_THICKNESS {
+                    map.tiles[map_idx(x, y)] = TileType::Floor;
+                }
+            }
+        }
+    }
+}
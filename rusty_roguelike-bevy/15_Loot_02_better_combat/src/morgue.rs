@@ -0,0 +1,174 @@
+use crate::components::Name;
+use crate::prelude::*;
+use ron::ser::{to_string_pretty, PrettyConfig};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const MORGUE_DIR: &str = "morgue";
+pub const HIGH_SCORES_FILE: &str = "highscores.ron";
+
+const MAX_HIGH_SCORES: usize = 10;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct HighScore {
+    pub score: u32,
+    pub outcome: String,
+    pub depth: u32,
+    pub turns: u32,
+    pub seed: u64,
+}
+
+// The best runs, in descending score order. It's inserted as resource when a run ends, so that it can be
+// shown by the end screen; its presence also signals that the run has already been recorded.
+//
+#[derive(Default, Serialize, Deserialize)]
+pub struct HighScores {
+    pub entries: Vec<HighScore>,
+    // Index of the entry of the run just ended, if it made the table.
+    #[serde(skip)]
+    pub latest: Option<usize>,
+}
+
+impl HighScores {
+    // A missing or invalid file is treated as an empty table, so that a corrupt file doesn't prevent
+    // recording new scores.
+    //
+    pub fn load() -> Self {
+        fs::read_to_string(HIGH_SCORES_FILE)
+            .ok()
+            .and_then(|serialized| ron::de::from_str(&serialized).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let serialized = to_string_pretty(self, PrettyConfig::default())
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error.to_string()))?;
+
+        fs::write(HIGH_SCORES_FILE, serialized)
+    }
+
+    // Ties are ranked by age, so that an older entry is not pushed down by a new one with the same score.
+    //
+    pub fn add(&mut self, entry: HighScore) {
+        let position = self
+            .entries
+            .iter()
+            .position(|other| other.score < entry.score)
+            .unwrap_or(self.entries.len());
+
+        self.entries.insert(position, entry);
+        self.entries.truncate(MAX_HIGH_SCORES);
+        self.latest = (position < MAX_HIGH_SCORES).then_some(position);
+    }
+}
+
+// Writes the morgue file of the run just ended, and adds it to the high scores, which are returned.
+// Failing to write the files is reported, but doesn't prevent starting a new run.
+//
+pub fn record_run(world: &mut World, victory: bool) -> HighScores {
+    let mut player_query = world.query_filtered::<&Experience, With<Player>>();
+    let character_level = player_query.iter(world).next().unwrap().level;
+
+    let run_stats = world.get_resource::<RunStats>().unwrap();
+    let seed = world.get_resource::<RunSeed>().unwrap().0;
+    let outcome = if victory {
+        "Retrieved the Amulet of Yala".to_string()
+    } else {
+        world
+            .get_resource::<DeathCause>()
+            .map_or_else(|| "Died".to_string(), |cause| cause.description())
+    };
+
+    let high_score = HighScore {
+        score: run_stats.score(victory),
+        outcome,
+        depth: run_stats.deepest_level + 1,
+        turns: run_stats.turns,
+        seed,
+    };
+
+    let morgue = format_morgue(world, &high_score, character_level);
+    if let Err(error) = write_morgue(&morgue) {
+        eprintln!("Unable to write the morgue file: {}", error);
+    }
+
+    let mut high_scores = HighScores::load();
+    high_scores.add(high_score);
+    if let Err(error) = high_scores.save() {
+        eprintln!("Unable to save the high scores: {}", error);
+    }
+
+    high_scores
+}
+
+fn format_morgue(world: &mut World, high_score: &HighScore, character_level: i32) -> String {
+    let mut carried_query = world.query_filtered::<(&Name, &Carried), With<Item>>();
+    let mut carried_items = carried_query
+        .iter(world)
+        .map(|(name, _)| name.0.clone())
+        .collect::<Vec<_>>();
+    carried_items.sort();
+
+    let run_stats = world.get_resource::<RunStats>().unwrap();
+    let identification = world.get_resource::<Identification>().unwrap();
+
+    // Writing to a String can't fail.
+    let mut morgue = String::new();
+    writeln!(morgue, "Dungeon Crawler - morgue file").unwrap();
+    writeln!(morgue).unwrap();
+    writeln!(morgue, "{}.", high_score.outcome).unwrap();
+    writeln!(morgue).unwrap();
+    writeln!(morgue, "Score: {}", high_score.score).unwrap();
+    writeln!(morgue, "Depth reached: {}", high_score.depth).unwrap();
+    writeln!(morgue, "Character level: {}", character_level).unwrap();
+    writeln!(morgue, "Turns: {}", high_score.turns).unwrap();
+    writeln!(morgue, "Seed: {}", high_score.seed).unwrap();
+
+    writeln!(morgue).unwrap();
+    writeln!(morgue, "Kills ({}):", run_stats.total_kills()).unwrap();
+    if run_stats.kills.is_empty() {
+        writeln!(morgue, "  None").unwrap();
+    }
+    for (name, count) in run_stats.kills.iter() {
+        writeln!(morgue, "  {} x{}", name, count).unwrap();
+    }
+
+    writeln!(morgue).unwrap();
+    writeln!(morgue, "Items used:").unwrap();
+    if run_stats.items_used.is_empty() {
+        writeln!(morgue, "  None").unwrap();
+    }
+    for (name, count) in run_stats.items_used.iter() {
+        writeln!(morgue, "  {} x{}", name, count).unwrap();
+    }
+
+    // The items are listed as the player knew them.
+    writeln!(morgue).unwrap();
+    writeln!(morgue, "Items carried:").unwrap();
+    if carried_items.is_empty() {
+        writeln!(morgue, "  None").unwrap();
+    }
+    for name in carried_items.iter() {
+        writeln!(morgue, "  {}", identification.display_name(name)).unwrap();
+    }
+
+    morgue
+}
+
+// Each run has its own file, named after the time it ended.
+//
+fn write_morgue(morgue: &str) -> io::Result<()> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+
+    fs::create_dir_all(MORGUE_DIR)?;
+    fs::write(
+        Path::new(MORGUE_DIR).join(format!("morgue-{}.txt", timestamp)),
+        morgue,
+    )
+}
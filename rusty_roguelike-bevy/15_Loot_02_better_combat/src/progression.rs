@@ -0,0 +1,63 @@
+use crate::prelude::*;
+
+// Gained on every level, in addition to the chosen perk.
+const LEVEL_HEALTH_BONUS: i32 = 2;
+const LEVEL_DAMAGE_BONUS: i32 = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Perk {
+    Toughness,
+    Might,
+    KeenEyes,
+    ThickSkin,
+}
+
+impl Perk {
+    pub const ALL: [Perk; 4] = [
+        Perk::Toughness,
+        Perk::Might,
+        Perk::KeenEyes,
+        Perk::ThickSkin,
+    ];
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Perk::Toughness => "Toughness: +5 max health",
+            Perk::Might => "Might: +1 damage",
+            Perk::KeenEyes => "Keen Eyes: +2 field of view",
+            Perk::ThickSkin => "Thick Skin: +1 armor",
+        }
+    }
+}
+
+// Raises the player level by one, applying the level bonuses and the chosen perk. The player is fully
+// healed, as a reward.
+//
+pub fn level_up(world: &mut World, perk: Perk) {
+    let mut player_query = world.query_filtered::<(
+        &mut Experience,
+        &mut Health,
+        &mut Damage,
+        &mut Armor,
+        &mut FieldOfView,
+    ), With<Player>>();
+
+    let (mut experience, mut health, mut damage, mut armor, mut fov) =
+        player_query.iter_mut(world).next().unwrap();
+
+    experience.level += 1;
+    health.max += LEVEL_HEALTH_BONUS;
+    damage.0 += LEVEL_DAMAGE_BONUS;
+
+    match perk {
+        Perk::Toughness => health.max += 5,
+        Perk::Might => damage.0 += 1,
+        Perk::KeenEyes => {
+            fov.radius += 2;
+            fov.is_dirty = true;
+        }
+        Perk::ThickSkin => armor.0 += 1,
+    }
+
+    health.current = health.max;
+}
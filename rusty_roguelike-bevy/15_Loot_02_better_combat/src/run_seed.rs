@@ -0,0 +1,65 @@
+use crate::prelude::*;
+
+// Spreads the stream indexes over the whole u64 range (it's the golden ratio fractional part, as used by
+// SplitMix64), so that neighbouring seeds don't share levels.
+const STREAM_MULTIPLIER: u64 = 0x9E37_79B9_7F4A_7C15;
+// Far from any level index.
+const APPEARANCE_STREAM: u64 = u64::MAX;
+
+// The seed of the current run. The RNG resource is reseeded from it on each level, so that every dungeon
+// level can be reproduced (passing `--seed`), independently of the player actions on the previous ones.
+//
+#[derive(Clone, Copy)]
+pub struct RunSeed(pub u64);
+
+impl RunSeed {
+    pub fn random() -> Self {
+        Self(RandomNumberGenerator::new().next_u64())
+    }
+
+    // Supports both `--seed <n>` and `--seed=<n>`; returns None if the option is not passed. Invalid values
+    // terminate the process, after printing the usage.
+    //
+    pub fn from_args() -> Option<Self> {
+        let mut args = std::env::args().skip(1);
+
+        while let Some(arg) = args.next() {
+            let value = if arg == "--seed" {
+                args.next()
+            } else {
+                arg.strip_prefix("--seed=").map(str::to_string)
+            };
+
+            if let Some(value) = value {
+                match value.parse() {
+                    Ok(seed) => return Some(Self(seed)),
+                    Err(_) => usage_error(&format!("Invalid seed: {}", value)),
+                }
+            } else if arg == "--seed" {
+                usage_error("Missing seed value");
+            }
+        }
+
+        None
+    }
+
+    pub fn level_rng(&self, map_level: u32) -> RandomNumberGenerator {
+        RandomNumberGenerator::seeded(self.stream_seed(map_level as u64))
+    }
+
+    // Separate from the level ones, so that the item appearances don't affect the levels generation.
+    //
+    pub fn appearance_rng(&self) -> RandomNumberGenerator {
+        RandomNumberGenerator::seeded(self.stream_seed(APPEARANCE_STREAM))
+    }
+
+    fn stream_seed(&self, stream: u64) -> u64 {
+        self.0 ^ stream.wrapping_mul(STREAM_MULTIPLIER)
+    }
+}
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("{}", message);
+    eprintln!("Usage: rusty_roguelike-bevy [--seed <n>]");
+    std::process::exit(2);
+}
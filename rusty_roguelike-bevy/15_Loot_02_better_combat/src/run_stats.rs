@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const LEVEL_SCORE: u32 = 100;
+const KILL_SCORE: u32 = 10;
+const VICTORY_SCORE: u32 = 1000;
+
+// Statistics of the current run, reported in the morgue file when the run ends. The maps are ordered, so
+// that they're listed alphabetically.
+//
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct RunStats {
+    // Player turns (actions), not rounds.
+    pub turns: u32,
+    pub deepest_level: u32,
+    // (monster name, kills)
+    pub kills: BTreeMap<String, u32>,
+    // (item name, uses); only the consumed items are counted.
+    pub items_used: BTreeMap<String, u32>,
+}
+
+impl RunStats {
+    pub fn add_kill(&mut self, name: &str) {
+        *self.kills.entry(name.to_string()).or_insert(0) += 1;
+    }
+
+    pub fn add_item_used(&mut self, name: &str) {
+        *self.items_used.entry(name.to_string()).or_insert(0) += 1;
+    }
+
+    pub fn total_kills(&self) -> u32 {
+        self.kills.values().sum()
+    }
+
+    pub fn score(&self, victory: bool) -> u32 {
+        let victory_score = if victory { VICTORY_SCORE } else { 0 };
+
+        (self.deepest_level + 1) * LEVEL_SCORE + self.total_kills() * KILL_SCORE + victory_score
+    }
+}
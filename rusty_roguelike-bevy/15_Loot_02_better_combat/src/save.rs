@@ -0,0 +1,436 @@
+use crate::components::Name;
+use crate::prelude::*;
+use ron::ser::{to_string_pretty, PrettyConfig};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+pub const SAVE_FILE: &str = "savegame.ron";
+
+// Bump this on every change to the saved structures; older files are rejected, rather than partially
+// loaded.
+const SAVE_VERSION: u32 = 18;
+
+// Bevy 0.7 doesn't provide (usable) scene serialization for arbitrary components, so we use an explicit
+// intermediate representation. This also has the advantage of decoupling the file format from the
+// components layout.
+//
+#[derive(Serialize, Deserialize)]
+struct SaveGame {
+    version: u32,
+    seed: u64,
+    map: Map,
+    theme: String,
+    entities: Vec<SavedEntity>,
+    level_store: LevelStore,
+    identification: Identification,
+    run_stats: RunStats,
+}
+
+// Read first, so that an incompatible file is detected before attempting to parse its content.
+//
+#[derive(Deserialize)]
+#[serde(rename = "SaveGame")]
+struct SaveHeader {
+    version: u32,
+}
+
+// Also used to store the levels not currently played.
+//
+#[derive(Clone, Serialize, Deserialize, Default)]
+pub struct SavedEntity {
+    player_map_level: Option<u32>,
+    position: Option<(i32, i32)>,
+    render: Option<SavedRender>,
+    name: Option<String>,
+    description: Option<String>,
+    enemy: bool,
+    item: bool,
+    equippable: Option<EquipmentSlot>,
+    equipped: bool,
+    amulet_of_yala: bool,
+    provides_healing: Option<i32>,
+    provides_dungeon_map: bool,
+    provides_food: Option<i32>,
+    key: bool,
+    provides_identification: bool,
+    provides_status_effects: Option<Vec<StatusEffect>>,
+    inflicts_status_effects: Option<Vec<StatusEffect>>,
+    status_effects: Option<Vec<StatusEffect>>,
+    moving_randomly: bool,
+    chasing_player: bool,
+    flees_when_hurt: Option<i32>,
+    kiting: Option<i32>,
+    // (post, radius)
+    guarding: Option<((i32, i32), i32)>,
+    // The path is not saved; a new one is computed on load.
+    patrolling: bool,
+    pack_member: bool,
+    asleep: Option<i32>,
+    health: Option<(i32, i32)>,
+    experience: Option<(i32, i32)>,
+    hunger: Option<i32>,
+    experience_value: Option<i32>,
+    damage: Option<i32>,
+    speed: Option<i32>,
+    energy: Option<i32>,
+    armor: Option<i32>,
+    range: Option<i32>,
+    area_of_effect: Option<i32>,
+    light_source: Option<i32>,
+    fuel: Option<i32>,
+    field_of_view: Option<i32>,
+    // Index of the carrier in the saved entities list, since Entity ids are not stable across runs.
+    carried_by: Option<usize>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct SavedRender {
+    fg: (f32, f32, f32, f32),
+    bg: (f32, f32, f32, f32),
+    glyph: FontCharType,
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(std::io::Error),
+    Format(String),
+    IncompatibleVersion { found: u32, expected: u32 },
+    UnknownTheme(String),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SaveError::Io(error) => write!(f, "I/O error: {}", error),
+            SaveError::Format(error) => write!(f, "invalid save file: {}", error),
+            SaveError::IncompatibleVersion { found, expected } => write!(
+                f,
+                "incompatible save file version {} (expected {})",
+                found, expected
+            ),
+            SaveError::UnknownTheme(name) => write!(f, "unknown map theme: {}", name),
+        }
+    }
+}
+
+impl From<std::io::Error> for SaveError {
+    fn from(error: std::io::Error) -> Self {
+        SaveError::Io(error)
+    }
+}
+
+pub fn save_exists() -> bool {
+    Path::new(SAVE_FILE).exists()
+}
+
+pub fn delete_save() {
+    // A missing file is not an error; there's simply nothing to delete.
+    let _ = fs::remove_file(SAVE_FILE);
+}
+
+pub fn save_game(world: &mut World) -> Result<(), SaveError> {
+    let mut entities_query = world.query::<Entity>();
+    let entities = entities_query.iter(world).collect::<Vec<_>>();
+
+    let save = SaveGame {
+        version: SAVE_VERSION,
+        seed: world.get_resource::<RunSeed>().unwrap().0,
+        map: world.get_resource::<Map>().unwrap().clone(),
+        theme: world
+            .get_resource::<Box<dyn MapTheme>>()
+            .unwrap()
+            .name()
+            .to_string(),
+        entities: snapshot_entities(world, &entities),
+        level_store: world.get_resource::<LevelStore>().unwrap().clone(),
+        identification: world.get_resource::<Identification>().unwrap().clone(),
+        run_stats: world.get_resource::<RunStats>().unwrap().clone(),
+    };
+
+    let serialized = to_string_pretty(&save, PrettyConfig::default())
+        .map_err(|error| SaveError::Format(error.to_string()))?;
+
+    fs::write(SAVE_FILE, serialized)?;
+
+    Ok(())
+}
+
+// On error, the world is left untouched.
+//
+pub fn load_game(world: &mut World) -> Result<(), SaveError> {
+    let serialized = fs::read_to_string(SAVE_FILE)?;
+
+    let header: SaveHeader =
+        ron::de::from_str(&serialized).map_err(|error| SaveError::Format(error.to_string()))?;
+
+    if header.version != SAVE_VERSION {
+        return Err(SaveError::IncompatibleVersion {
+            found: header.version,
+            expected: SAVE_VERSION,
+        });
+    }
+
+    let save: SaveGame =
+        ron::de::from_str(&serialized).map_err(|error| SaveError::Format(error.to_string()))?;
+
+    let theme = world
+        .get_resource::<Themes>()
+        .unwrap()
+        .theme(&save.theme)
+        .ok_or(SaveError::UnknownTheme(save.theme))?;
+
+    world.clear_entities();
+
+    restore_entities(world, &save.entities);
+
+    let mut player_query = world.query::<(&Player, &PointC)>();
+    let (player, player_pos) = player_query.iter(world).next().unwrap();
+    let (player_map_level, player_pos) = (player.map_level, player_pos.0);
+
+    world.insert_resource(save.map);
+    world.insert_resource(Camera::new(player_pos));
+    world.insert_resource(TurnState::AwaitingInput);
+    world.insert_resource(theme);
+    world.insert_resource(save.level_store);
+    world.insert_resource(save.identification);
+    world.insert_resource(save.run_stats);
+    // The log is not saved.
+    world.insert_resource(GameLog::default());
+    // The RNG state is not saved; reseeding is enough to keep the run (loosely) reproducible.
+    let seed = RunSeed(save.seed);
+    world.insert_resource(seed.level_rng(player_map_level));
+    world.insert_resource(seed);
+    world.remove_resource::<VirtualKeyCode>();
+    world.remove_resource::<AutoMove>();
+
+    Ok(())
+}
+
+// Converts the given entities to their serializable representation; relationships between entities
+// (e.g. carried items) are preserved only if both entities are in the list.
+//
+pub fn snapshot_entities(world: &World, entities: &[Entity]) -> Vec<SavedEntity> {
+    entities
+        .iter()
+        .map(|entity| {
+            let entity_ref = world.entity(*entity);
+            let render = entity_ref.get::<Render>().map(|render| SavedRender {
+                fg: rgba_to_tuple(render.color.fg),
+                bg: rgba_to_tuple(render.color.bg),
+                glyph: render.glyph,
+            });
+
+            SavedEntity {
+                player_map_level: entity_ref.get::<Player>().map(|p| p.map_level),
+                position: entity_ref.get::<PointC>().map(|p| (p.0.x, p.0.y)),
+                render,
+                name: entity_ref.get::<Name>().map(|n| n.0.clone()),
+                description: entity_ref.get::<Description>().map(|d| d.0.clone()),
+                enemy: entity_ref.contains::<Enemy>(),
+                item: entity_ref.contains::<Item>(),
+                equippable: entity_ref.get::<Equippable>().map(|e| e.slot),
+                equipped: entity_ref.contains::<Equipped>(),
+                amulet_of_yala: entity_ref.contains::<AmuletOfYala>(),
+                provides_healing: entity_ref.get::<ProvidesHealing>().map(|h| h.amount),
+                provides_dungeon_map: entity_ref.contains::<ProvidesDungeonMap>(),
+                provides_food: entity_ref.get::<ProvidesFood>().map(|f| f.amount),
+                key: entity_ref.contains::<Key>(),
+                provides_identification: entity_ref.contains::<ProvidesIdentification>(),
+                provides_status_effects: entity_ref
+                    .get::<ProvidesStatusEffects>()
+                    .map(|p| p.0.clone()),
+                inflicts_status_effects: entity_ref
+                    .get::<InflictsStatusEffects>()
+                    .map(|i| i.0.clone()),
+                status_effects: entity_ref.get::<StatusEffects>().map(|s| s.0.clone()),
+                moving_randomly: entity_ref.contains::<MovingRandomly>(),
+                chasing_player: entity_ref.contains::<ChasingPlayer>(),
+                flees_when_hurt: entity_ref
+                    .get::<FleesWhenHurt>()
+                    .map(|f| f.health_threshold),
+                kiting: entity_ref.get::<Kiting>().map(|k| k.range),
+                guarding: entity_ref
+                    .get::<Guarding>()
+                    .map(|g| ((g.post.x, g.post.y), g.radius)),
+                patrolling: entity_ref.contains::<Patrolling>(),
+                pack_member: entity_ref.contains::<PackMember>(),
+                asleep: entity_ref.get::<Asleep>().map(|a| a.hearing),
+                health: entity_ref.get::<Health>().map(|h| (h.current, h.max)),
+                experience: entity_ref.get::<Experience>().map(|e| (e.level, e.xp)),
+                hunger: entity_ref.get::<Hunger>().map(|h| h.food),
+                experience_value: entity_ref.get::<ExperienceValue>().map(|e| e.0),
+                damage: entity_ref.get::<Damage>().map(|d| d.0),
+                speed: entity_ref.get::<Speed>().map(|s| s.0),
+                energy: entity_ref.get::<Energy>().map(|e| e.0),
+                armor: entity_ref.get::<Armor>().map(|a| a.0),
+                range: entity_ref.get::<Ranged>().map(|r| r.range),
+                area_of_effect: entity_ref.get::<AreaOfEffect>().map(|a| a.radius),
+                light_source: entity_ref.get::<LightSource>().map(|l| l.radius),
+                fuel: entity_ref.get::<Fuel>().map(|f| f.0),
+                field_of_view: entity_ref.get::<FieldOfView>().map(|fov| fov.radius),
+                carried_by: entity_ref
+                    .get::<Carried>()
+                    .and_then(|c| entities.iter().position(|e| *e == c.0)),
+            }
+        })
+        .collect()
+}
+
+// Returns the spawned entities, in the same order as the saved ones.
+//
+pub fn restore_entities(world: &mut World, saved_entities: &[SavedEntity]) -> Vec<Entity> {
+    // Carried relationships are restored in a second pass, since the carrier may be spawned after the
+    // item.
+    let spawned_entities = saved_entities
+        .iter()
+        .map(|saved| {
+            let mut entity = world.spawn();
+
+            if let Some(map_level) = saved.player_map_level {
+                entity.insert(Player { map_level });
+            }
+            if let Some((x, y)) = saved.position {
+                entity.insert(PointC(Point::new(x, y)));
+            }
+            if let Some(render) = &saved.render {
+                entity.insert(Render {
+                    color: ColorPair::new(tuple_to_rgba(render.fg), tuple_to_rgba(render.bg)),
+                    glyph: render.glyph,
+                });
+            }
+            if let Some(name) = &saved.name {
+                entity.insert(Name(name.clone()));
+            }
+            if let Some(description) = &saved.description {
+                entity.insert(Description(description.clone()));
+            }
+            if saved.enemy {
+                entity.insert(Enemy);
+            }
+            if saved.item {
+                entity.insert(Item);
+            }
+            if let Some(slot) = saved.equippable {
+                entity.insert(Equippable { slot });
+            }
+            if saved.equipped {
+                entity.insert(Equipped);
+            }
+            if saved.amulet_of_yala {
+                entity.insert(AmuletOfYala);
+            }
+            if let Some(amount) = saved.provides_healing {
+                entity.insert(ProvidesHealing { amount });
+            }
+            if saved.provides_dungeon_map {
+                entity.insert(ProvidesDungeonMap);
+            }
+            if let Some(amount) = saved.provides_food {
+                entity.insert(ProvidesFood { amount });
+            }
+            if saved.key {
+                entity.insert(Key);
+            }
+            if saved.provides_identification {
+                entity.insert(ProvidesIdentification);
+            }
+            if let Some(effects) = &saved.provides_status_effects {
+                entity.insert(ProvidesStatusEffects(effects.clone()));
+            }
+            if let Some(effects) = &saved.inflicts_status_effects {
+                entity.insert(InflictsStatusEffects(effects.clone()));
+            }
+            if let Some(effects) = &saved.status_effects {
+                entity.insert(StatusEffects(effects.clone()));
+            }
+            if saved.moving_randomly {
+                entity.insert(MovingRandomly);
+            }
+            if saved.chasing_player {
+                entity.insert(ChasingPlayer);
+            }
+            if let Some(health_threshold) = saved.flees_when_hurt {
+                entity.insert(FleesWhenHurt { health_threshold });
+            }
+            if let Some(range) = saved.kiting {
+                entity.insert(Kiting { range });
+            }
+            if let Some(((x, y), radius)) = saved.guarding {
+                entity.insert(Guarding {
+                    post: Point::new(x, y),
+                    radius,
+                });
+            }
+            if saved.patrolling {
+                entity.insert(Patrolling::default());
+            }
+            if saved.pack_member {
+                entity.insert(PackMember);
+            }
+            if let Some(hearing) = saved.asleep {
+                entity.insert(Asleep { hearing });
+            }
+            if let Some((current, max)) = saved.health {
+                entity.insert(Health { current, max });
+            }
+            if let Some((level, xp)) = saved.experience {
+                entity.insert(Experience { level, xp });
+            }
+            if let Some(food) = saved.hunger {
+                entity.insert(Hunger { food });
+            }
+            if let Some(xp) = saved.experience_value {
+                entity.insert(ExperienceValue(xp));
+            }
+            if let Some(damage) = saved.damage {
+                entity.insert(Damage(damage));
+            }
+            if let Some(speed) = saved.speed {
+                entity.insert(Speed(speed));
+            }
+            if let Some(energy) = saved.energy {
+                entity.insert(Energy(energy));
+            }
+            if let Some(armor) = saved.armor {
+                entity.insert(Armor(armor));
+            }
+            if let Some(range) = saved.range {
+                entity.insert(Ranged { range });
+            }
+            if let Some(radius) = saved.area_of_effect {
+                entity.insert(AreaOfEffect { radius });
+            }
+            if let Some(radius) = saved.light_source {
+                entity.insert(LightSource { radius });
+            }
+            if let Some(fuel) = saved.fuel {
+                entity.insert(Fuel(fuel));
+            }
+            if let Some(radius) = saved.field_of_view {
+                entity.insert(FieldOfView::new(radius));
+            }
+
+            entity.id()
+        })
+        .collect::<Vec<_>>();
+
+    for (saved, entity) in saved_entities.iter().zip(spawned_entities.iter()) {
+        if let Some(carrier_index) = saved.carried_by {
+            world
+                .entity_mut(*entity)
+                .insert(Carried(spawned_entities[carrier_index]));
+        }
+    }
+
+    spawned_entities
+}
+
+fn rgba_to_tuple(color: RGBA) -> (f32, f32, f32, f32) {
+    (color.r, color.g, color.b, color.a)
+}
+
+fn tuple_to_rgba((r, g, b, a): (f32, f32, f32, f32)) -> RGBA {
+    RGBA::from_f32(r, g, b, a)
+}
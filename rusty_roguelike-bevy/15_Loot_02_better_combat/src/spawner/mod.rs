@@ -1,32 +1,90 @@
 use crate::prelude::*;
 mod template;
-use template::Templates;
+pub use template::Templates;
+
+const TORCH_RADIUS: i32 = 5;
 
 pub fn spawn_player(ecs: &mut World, pos: Point) {
-    ecs.spawn().insert_bundle((
-        Player { map_level: 0 },
-        PointC(pos),
-        Render {
-            color: ColorPair::new(WHITE, BLACK),
-            glyph: to_cp437('@'),
-        },
-        Health {
-            current: 10,
-            max: 10,
-        },
-        FieldOfView::new(8),
-        Damage(1),
-    ));
+    let player = ecs
+        .spawn()
+        .insert_bundle((
+            Player { map_level: 0 },
+            PointC(pos),
+            Render {
+                color: ColorPair::new(WHITE, BLACK),
+                glyph: to_cp437('@'),
+            },
+            Health {
+                current: 10,
+                max: 10,
+            },
+            FieldOfView::new(8),
+            Damage(1),
+            Armor(0),
+            StatusEffects::default(),
+            Experience { level: 1, xp: 0 },
+            Hunger {
+                food: STARTING_FOOD,
+            },
+            Speed(NORMAL_SPEED),
+            // The player acts first.
+            Energy(ACTION_COST),
+        ))
+        .id();
+
+    // The player starts with a torch in hand.
+    let torch = ecs
+        .resource_scope(|ecs, templates: Mut<Templates>| templates.spawn_named(ecs, "Torch", &pos));
+    let mut torch = ecs.entity_mut(torch);
+    torch.remove::<PointC>();
+    torch.insert_bundle((Carried(player), Equipped));
+}
+
+// Potions and scrolls look different on each run, until they're identified.
+//
+pub fn randomize_appearances(
+    templates: &Templates,
+    rng: &mut RandomNumberGenerator,
+) -> Identification {
+    let items = templates
+        .entities
+        .iter()
+        .filter_map(|t| t.unidentified.map(|class| (t.name.clone(), class)))
+        .collect::<Vec<_>>();
+
+    Identification::new(&items, rng)
 }
 
 pub fn spawn_level(
     ecs: &mut World,
     rng: &mut RandomNumberGenerator,
     level: usize,
-    spawn_points: &[Point],
+    monster_spawn_points: &[Point],
+    item_spawn_points: &[Point],
+    key_spawn_points: &[Point],
+    torch_spawn_points: &[Point],
 ) {
-    let template = Templates::load();
-    template.spawn_entities(ecs, rng, level, spawn_points);
+    ecs.resource_scope(|ecs, templates: Mut<Templates>| {
+        templates.spawn_entities(ecs, rng, level, monster_spawn_points, item_spawn_points);
+
+        for pt in key_spawn_points.iter() {
+            templates.spawn_named(ecs, "Key", pt);
+        }
+    });
+
+    for pt in torch_spawn_points.iter() {
+        ecs.spawn().insert_bundle((
+            PointC(*pt),
+            Render {
+                color: ColorPair::new(ORANGE, BLACK),
+                glyph: to_cp437('*'),
+            },
+            Name("Wall Torch".to_string()),
+            LightSource {
+                radius: TORCH_RADIUS,
+            },
+        ));
+    }
 }
 
 pub fn spawn_amulet_of_yala(world: &mut World, pos: Point) {
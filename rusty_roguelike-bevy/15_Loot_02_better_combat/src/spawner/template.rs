@@ -11,9 +11,45 @@ pub struct Template {
     pub frequency: i32,
     pub name: String,
     pub glyph: char,
+    pub description: Option<String>,
+    // Status effects are provided (and inflicted) as (name, duration) pairs.
     pub provides: Option<Vec<(String, i32)>>,
+    pub inflicts: Option<Vec<(String, i32)>>,
     pub hp: Option<i32>,
+    pub behaviors: Option<Vec<Behavior>>,
+    // Defaults to NORMAL_SPEED.
+    pub speed: Option<i32>,
+    pub xp: Option<i32>,
     pub base_damage: Option<i32>,
+    // Items with base damage are implicitly weapons, unless they're ranged.
+    pub slot: Option<EquipmentSlot>,
+    pub armor: Option<i32>,
+    // Ranged items are used on a target; if they're not equippable, they're thrown (or cast), and their
+    // damage and effects apply to the target area.
+    pub range: Option<i32>,
+    pub area_of_effect: Option<i32>,
+    pub light_radius: Option<i32>,
+    // Light sources without fuel never burn out.
+    pub fuel: Option<i32>,
+    // Items of these classes are shown with a random appearance, until they're identified.
+    pub unidentified: Option<ItemClass>,
+}
+
+// Monsters without behaviors chase the player.
+#[derive(Clone, Deserialize, Debug, PartialEq)]
+pub enum Behavior {
+    Chasing,
+    MovingRandomly,
+    // Health threshold.
+    Fleeing(i32),
+    // Attack range.
+    Kiting(i32),
+    // Radius around the spawn point.
+    Guarding(i32),
+    Patrolling,
+    Pack,
+    // Hearing radius.
+    Sleeping(i32),
 }
 
 #[derive(Clone, Deserialize, Debug, PartialEq)]
@@ -38,26 +74,51 @@ impl Templates {
         ecs: &mut World,
         rng: &mut RandomNumberGenerator,
         level: usize,
-        spawn_points: &[Point],
+        monster_spawn_points: &[Point],
+        item_spawn_points: &[Point],
     ) {
-        let mut available_entities = Vec::new();
-        for t in self.entities.iter() {
-            if t.levels.contains(&level) {
-                for _ in 0..t.frequency {
-                    available_entities.push(t);
-                }
+        let available_monsters = self.available_entities(level, EntityType::Enemy);
+        let available_items = self.available_entities(level, EntityType::Item);
+
+        for pt in monster_spawn_points.iter() {
+            if let Some(entity) = rng.random_slice_entry(&available_monsters) {
+                self.spawn_entity(pt, entity, ecs);
             }
         }
 
-        for pt in spawn_points.iter() {
-            if let Some(entity) = rng.random_slice_entry(&available_entities) {
+        for pt in item_spawn_points.iter() {
+            if let Some(entity) = rng.random_slice_entry(&available_items) {
                 self.spawn_entity(pt, entity, ecs);
             }
         }
         // We don't need flushing; when manipulating World directly in Bevy, flushes are implicit.
     }
 
-    fn spawn_entity(&self, pt: &Point, template: &Template, world: &mut World) {
+    // Spawns an entity regardless of its levels/frequency (e.g. the keys, which are placed by the map
+    // builder).
+    //
+    pub fn spawn_named(&self, ecs: &mut World, name: &str, pt: &Point) -> Entity {
+        let template = self
+            .entities
+            .iter()
+            .find(|t| t.name == name)
+            .unwrap_or_else(|| panic!("Template {} not found", name));
+        self.spawn_entity(pt, template, ecs)
+    }
+
+    fn available_entities(&self, level: usize, entity_type: EntityType) -> Vec<&Template> {
+        let mut available_entities = Vec::new();
+        for t in self.entities.iter() {
+            if t.levels.contains(&level) && t.entity_type == entity_type {
+                for _ in 0..t.frequency {
+                    available_entities.push(t);
+                }
+            }
+        }
+        available_entities
+    }
+
+    fn spawn_entity(&self, pt: &Point, template: &Template, world: &mut World) -> Entity {
         let mut world_spawner = world.spawn();
         let entity = world_spawner.insert_bundle((
             PointC(*pt),
@@ -77,14 +138,54 @@ impl Templates {
             EntityType::Enemy => {
                 entity.insert(Enemy {});
                 entity.insert(FieldOfView::new(6));
-                entity.insert(ChasingPlayer {});
+                entity.insert(StatusEffects::default());
+                entity.insert(Speed(template.speed.unwrap_or(NORMAL_SPEED)));
+                entity.insert(Energy(0));
                 entity.insert(Health {
                     current: template.hp.unwrap(),
                     max: template.hp.unwrap(),
                 });
+
+                let default_behaviors = vec![Behavior::Chasing];
+                let behaviors = template.behaviors.as_ref().unwrap_or(&default_behaviors);
+
+                for behavior in behaviors.iter() {
+                    match behavior {
+                        Behavior::Chasing => {
+                            entity.insert(ChasingPlayer);
+                        }
+                        Behavior::MovingRandomly => {
+                            entity.insert(MovingRandomly);
+                        }
+                        Behavior::Fleeing(health_threshold) => {
+                            entity.insert(FleesWhenHurt {
+                                health_threshold: *health_threshold,
+                            });
+                        }
+                        Behavior::Kiting(range) => {
+                            entity.insert(Kiting { range: *range });
+                        }
+                        Behavior::Guarding(radius) => {
+                            entity.insert(Guarding {
+                                post: *pt,
+                                radius: *radius,
+                            });
+                        }
+                        Behavior::Patrolling => {
+                            entity.insert(Patrolling::default());
+                        }
+                        Behavior::Pack => {
+                            entity.insert(PackMember);
+                        }
+                        Behavior::Sleeping(hearing) => {
+                            entity.insert(Asleep { hearing: *hearing });
+                        }
+                    }
+                }
             }
         }
         if let Some(effects) = &template.provides {
+            let mut status_effects = Vec::new();
             for (provides, n) in effects.iter() {
                 match provides.as_str() {
                     "Healing" => {
@@ -93,17 +194,73 @@ impl Templates {
                     "MagicMap" => {
                         entity.insert(ProvidesDungeonMap {});
                     }
-                    _ => {
-                        println!("Warning: we don't know how to provide {}", provides);
+                    "Food" => {
+                        entity.insert(ProvidesFood { amount: *n });
+                    }
+                    "Key" => {
+                        entity.insert(Key);
                     }
+                    "Identify" => {
+                        entity.insert(ProvidesIdentification);
+                    }
+                    other => {
+                        if let Some(kind) = StatusEffectKind::from_name(other) {
+                            status_effects.push(StatusEffect { kind, turns: *n });
+                        } else {
+                            println!("Warning: we don't know how to provide {}", provides);
+                        }
+                    }
+                }
+            }
+            if !status_effects.is_empty() {
+                entity.insert(ProvidesStatusEffects(status_effects));
+            }
+        }
+        if let Some(effects) = &template.inflicts {
+            let mut status_effects = Vec::new();
+            for (inflicts, n) in effects.iter() {
+                if let Some(kind) = StatusEffectKind::from_name(inflicts) {
+                    status_effects.push(StatusEffect { kind, turns: *n });
+                } else {
+                    println!("Warning: we don't know how to inflict {}", inflicts);
                 }
             }
+            entity.insert(InflictsStatusEffects(status_effects));
+        }
+        if let Some(description) = &template.description {
+            entity.insert(Description(description.clone()));
         }
         if let Some(damage) = &template.base_damage {
             entity.insert(Damage(*damage));
-            if template.entity_type == EntityType::Item {
-                entity.insert(Weapon {});
+        }
+        if let Some(xp) = &template.xp {
+            entity.insert(ExperienceValue(*xp));
+        }
+        if let Some(armor) = &template.armor {
+            entity.insert(Armor(*armor));
+        }
+        if let Some(range) = &template.range {
+            entity.insert(Ranged { range: *range });
+        }
+        if let Some(radius) = &template.area_of_effect {
+            entity.insert(AreaOfEffect { radius: *radius });
+        }
+        if let Some(radius) = &template.light_radius {
+            entity.insert(LightSource { radius: *radius });
+        }
+        if let Some(fuel) = &template.fuel {
+            entity.insert(Fuel(*fuel));
+        }
+        if template.entity_type == EntityType::Item {
+            let slot = template.slot.or_else(|| {
+                (template.base_damage.is_some() && template.range.is_none())
+                    .then_some(EquipmentSlot::Weapon)
+            });
+            if let Some(slot) = slot {
+                entity.insert(Equippable { slot });
             }
         }
+
+        entity.id()
     }
 }
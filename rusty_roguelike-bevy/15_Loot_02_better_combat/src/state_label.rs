@@ -1,8 +1,14 @@
 use crate::prelude::*;
 
-// Only Fov is currently required; a String works as well, but this is the clean approach.
+// A String works as well, but this is the clean approach.
 //
 #[derive(Debug, Clone, PartialEq, Eq, Hash, SystemLabel)]
 pub enum StateLabel {
     Fov,
+    // The movement may damage the creatures (see the traps), so it must precede end_turn.
+    Movement,
+    // The random moves consume the RNG, so they run in a fixed order relative to the other behaviors
+    // that do, for the seeded runs to be reproducible.
+    RandomMoves,
+    MonsterMoves,
 }
@@ -0,0 +1,82 @@
+use crate::prelude::*;
+
+// Logic shared by the monster behavior systems.
+
+// Confusion (see random_move) and fleeing (see fleeing) take priority over the other behaviors.
+//
+pub fn is_overridden(
+    status_effects: &StatusEffects,
+    flees_when_hurt: Option<&FleesWhenHurt>,
+    health: &Health,
+) -> bool {
+    status_effects.has(StatusEffectKind::Confusion)
+        || flees_when_hurt.is_some_and(|flees| flees.is_fleeing(health))
+}
+
+pub fn dijkstra_map_to(target: Point, map: &Map) -> DijkstraMap {
+    DijkstraMap::new(
+        SCREEN_WIDTH,
+        SCREEN_HEIGHT,
+        &[map.point2d_to_index(target)],
+        map,
+        1024.0,
+    )
+}
+
+// Same logic as the chasing system: when adjacent, the step is the target itself.
+//
+pub fn step_towards(
+    pos: Point,
+    target: Point,
+    dijkstra_map: &DijkstraMap,
+    map: &Map,
+) -> Option<Point> {
+    if DistanceAlg::Pythagoras.distance2d(pos, target) <= MELEE_RANGE {
+        Some(target)
+    } else {
+        DijkstraMap::find_lowest_exit(dijkstra_map, map.point2d_to_index(pos), map)
+            .map(|idx| map.index_to_point2d(idx))
+    }
+}
+
+// Returns None if there's no tile farther from the target.
+//
+pub fn step_away(pos: Point, dijkstra_map: &DijkstraMap, map: &Map) -> Option<Point> {
+    let idx = map.point2d_to_index(pos);
+    DijkstraMap::find_highest_exit(dijkstra_map, idx, map)
+        .filter(|exit_idx| dijkstra_map.map[*exit_idx] > dijkstra_map.map[idx])
+        .map(|exit_idx| map.index_to_point2d(exit_idx))
+}
+
+// Attacks the player if they're on the destination; otherwise, moves there, unless it's occupied by
+// another creature (enemies don't attack each other).
+//
+pub fn move_or_attack(
+    entity: Entity,
+    destination: Point,
+    player_entity: Entity,
+    positions: &Query<(Entity, &PointC), With<Health>>,
+    move_events: &mut EventWriter<WantsToMove>,
+    attack_events: &mut EventWriter<WantsToAttack>,
+) {
+    let mut occupied = false;
+    for (victim, target_pos) in positions.iter() {
+        if target_pos.0 == destination {
+            if victim == player_entity {
+                attack_events.send(WantsToAttack {
+                    attacker: entity,
+                    victim,
+                    ranged: false,
+                });
+            }
+            occupied = true;
+        }
+    }
+
+    if !occupied {
+        move_events.send(WantsToMove {
+            entity,
+            destination,
+        });
+    }
+}
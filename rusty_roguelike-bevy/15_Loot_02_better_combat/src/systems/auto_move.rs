@@ -0,0 +1,121 @@
+use crate::components::Name;
+use crate::prelude::*;
+
+// Handles the click-to-travel, and takes the automatic steps (see AutoMove). Explore is started by
+// player_input.
+//
+pub fn auto_move(
+    mut commands: Commands,
+    mut move_events: EventWriter<WantsToMove>,
+    player_query: Query<(Entity, &PointC, &FieldOfView, &StatusEffects), With<Player>>,
+    enemies_query: Query<(Entity, &PointC, &Name), With<Enemy>>,
+    auto_move: Option<Res<AutoMove>>,
+    key: Option<Res<VirtualKeyCode>>,
+    (mouse_pos, mouse_click, camera, map, light_map): (
+        Res<Point>,
+        Res<MouseClick>,
+        Res<Camera>,
+        Res<Map>,
+        Res<LightMap>,
+    ),
+    mut game_log: ResMut<GameLog>,
+) {
+    // Keypresses are handled by player_input, and interrupt the movement.
+    if key.is_some() {
+        return;
+    }
+
+    let (player_entity, player_pos, player_fov, status_effects) = player_query.single();
+    let mut visible_enemies = enemies_query
+        .iter()
+        .filter(|(_, pos, _)| light_map.can_see(player_pos.0, player_fov, pos.0));
+
+    // Only revealed tiles can be traveled to; the first step is taken on the next frame.
+    //
+    if mouse_click.0 {
+        let destination = *mouse_pos + Point::new(camera.left_x, camera.top_y);
+
+        if map.in_bounds(destination)
+            && map.revealed_tiles[map.point2d_to_index(destination)]
+            && map.can_enter_tile(destination)
+            && destination != player_pos.0
+        {
+            commands.insert_resource(AutoMove {
+                destination: Some(destination),
+                known_enemies: visible_enemies.map(|(entity, _, _)| entity).collect(),
+            });
+        }
+        return;
+    }
+
+    let auto_move = match auto_move {
+        Some(auto_move) => auto_move,
+        None => return,
+    };
+
+    if let Some((_, _, name)) =
+        visible_enemies.find(|(entity, _, _)| !auto_move.known_enemies.contains(entity))
+    {
+        game_log.add(format!("You stop, as you spot the {}.", name.0), YELLOW);
+        commands.remove_resource::<AutoMove>();
+        return;
+    }
+
+    if status_effects.has(StatusEffectKind::Confusion) {
+        game_log.add("You're too confused to find your way.", MAGENTA);
+        commands.remove_resource::<AutoMove>();
+        return;
+    }
+
+    let revealed_map = RevealedMap(map.as_ref());
+    let player_idx = map.point2d_to_index(player_pos.0);
+
+    let targets = match auto_move.destination {
+        Some(destination) => vec![map.point2d_to_index(destination)],
+        None => {
+            let mut frontier = revealed_map.frontier();
+            frontier.retain(|idx| *idx != player_idx);
+            frontier
+        }
+    };
+
+    let dijkstra_map = DijkstraMap::new(
+        SCREEN_WIDTH,
+        SCREEN_HEIGHT,
+        &targets,
+        &revealed_map,
+        MAX_PATH_DEPTH,
+    );
+    let player_distance = dijkstra_map.map[player_idx];
+
+    if player_distance == 0.0 {
+        commands.remove_resource::<AutoMove>();
+        return;
+    }
+
+    let next_step = DijkstraMap::find_lowest_exit(&dijkstra_map, player_idx, &revealed_map)
+        .filter(|idx| dijkstra_map.map[*idx] < player_distance)
+        .map(|idx| map.index_to_point2d(idx));
+
+    match next_step {
+        // Enemies are never walked into, since it would be an attack.
+        Some(destination) if !enemies_query.iter().any(|(_, pos, _)| pos.0 == destination) => {
+            move_events.send(WantsToMove {
+                entity: player_entity,
+                destination,
+            });
+            commands.insert_resource(TurnState::PlayerTurn);
+        }
+        Some(_) => {
+            commands.remove_resource::<AutoMove>();
+        }
+        None => {
+            if auto_move.destination.is_some() {
+                game_log.add("You can't find a way there.", GRAY);
+            } else {
+                game_log.add("There's nothing left to explore.", GRAY);
+            }
+            commands.remove_resource::<AutoMove>();
+        }
+    }
+}
@@ -1,9 +1,20 @@
+use super::ai::is_overridden;
 use crate::prelude::*;
 
 pub fn chasing(
     mut move_events: EventWriter<WantsToMove>,
     mut attack_events: EventWriter<WantsToAttack>,
-    movers: Query<(Entity, &PointC, &FieldOfView), With<ChasingPlayer>>,
+    movers: Query<
+        (
+            Entity,
+            &PointC,
+            &FieldOfView,
+            &Health,
+            Option<&FleesWhenHurt>,
+            &StatusEffects,
+        ),
+        (With<ChasingPlayer>, With<Acting>, Without<Asleep>),
+    >,
     positions: Query<(Entity, &PointC), With<Health>>,
     player: Query<&PointC, With<Player>>,
     map: Res<Map>,
@@ -20,15 +31,17 @@ pub fn chasing(
         1024.0,
     );
 
-    for (entity, pos, fov) in movers.iter() {
-        if !fov.visible_tiles.contains(&player_pos) {
+    for (entity, pos, fov, health, flees_when_hurt, status_effects) in movers.iter() {
+        if !fov.visible_tiles.contains(&player_pos)
+            || is_overridden(status_effects, flees_when_hurt, health)
+        {
             continue;
         }
 
         let idx = map_idx(pos.0.x, pos.0.y);
         if let Some(destination) = DijkstraMap::find_lowest_exit(&dijkstra_map, idx, map.as_ref()) {
             let distance = DistanceAlg::Pythagoras.distance2d(pos.0, player_pos);
-            let destination = if distance > 1.2 {
+            let destination = if distance > MELEE_RANGE {
                 map.index_to_point2d(destination)
             } else {
                 player_pos
@@ -41,6 +54,7 @@ pub fn chasing(
                         attack_events.send(WantsToAttack {
                             attacker: entity,
                             victim: victim,
+                            ranged: false,
                         });
                     }
                     attacked = true;
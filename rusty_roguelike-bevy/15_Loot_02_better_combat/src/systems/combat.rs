@@ -1,16 +1,37 @@
+use crate::components::Name;
 use crate::prelude::*;
 
+const STRENGTH_DAMAGE_BONUS: i32 = 1;
+
 pub fn combat(
     mut commands: Commands,
     mut attack_events: EventReader<WantsToAttack>,
     mut health_query: Query<&mut Health>,
     player_query: Query<&Player>,
     base_damage_query: Query<&Damage>,
-    carried_weapons_query: Query<(&Carried, &Damage)>,
+    base_armor_query: Query<&Armor>,
+    equipped_query: Query<
+        (&Carried, Option<&Damage>, Option<&Armor>, Option<&Ranged>),
+        With<Equipped>,
+    >,
+    inflicts_query: Query<&InflictsStatusEffects>,
+    mut status_effects_query: Query<&mut StatusEffects>,
+    mut experience_query: Query<&mut Experience>,
+    experience_value_query: Query<&ExperienceValue>,
+    names_query: Query<&Name>,
+    mut run_stats: ResMut<RunStats>,
+    mut game_log: ResMut<GameLog>,
 ) {
     // We can conveniently iterate the message reader, and destructure the message.
-    for WantsToAttack { attacker, victim } in attack_events.iter() {
+    for WantsToAttack {
+        attacker,
+        victim,
+        ranged,
+    } in attack_events.iter()
+    {
         let is_player = player_query.get(*victim).is_ok();
+        let attacker_name = entity_name(*attacker, &names_query);
+        let victim_name = entity_name(*victim, &names_query);
 
         let base_damage = if let Ok(dmg) = base_damage_query.get(*attacker) {
             dmg.0
@@ -18,18 +39,143 @@ pub fn combat(
             0
         };
 
-        let weapon_damage: i32 = carried_weapons_query
+        let strength_bonus = match status_effects_query.get(*attacker) {
+            Ok(status_effects) if status_effects.has(StatusEffectKind::Strength) => {
+                STRENGTH_DAMAGE_BONUS
+            }
+            _ => 0,
+        };
+
+        // (damage, ranged)
+        let weapons = equipped_query
             .iter()
-            .filter_map(|(carried, dmg)| (carried.0 == *attacker).then(|| dmg.0))
+            .filter(|(carried, _, _, _)| carried.0 == *attacker)
+            .map(|(_, dmg, _, weapon_range)| (dmg.map_or(0, |d| d.0), weapon_range.is_some()))
+            .collect::<Vec<_>>();
+
+        // Shots only deal the ranged weapon damage; the creatures without one (e.g. the monsters) use
+        // their own damage, as in melee, where the ranged weapons don't contribute.
+        //
+        let (base_damage, strength_bonus, weapon_damage) =
+            if *ranged && weapons.iter().any(|(_, is_ranged)| *is_ranged) {
+                let ranged_damage: i32 = weapons
+                    .iter()
+                    .filter(|(_, is_ranged)| *is_ranged)
+                    .map(|(dmg, _)| dmg)
+                    .sum();
+                (0, 0, ranged_damage)
+            } else {
+                let melee_damage: i32 = weapons
+                    .iter()
+                    .filter(|(_, is_ranged)| !*is_ranged)
+                    .map(|(dmg, _)| dmg)
+                    .sum();
+                (base_damage, strength_bonus, melee_damage)
+            };
+
+        let base_armor = if let Ok(armor) = base_armor_query.get(*victim) {
+            armor.0
+        } else {
+            0
+        };
+
+        let equipped_armor: i32 = equipped_query
+            .iter()
+            .filter(|(carried, _, _, _)| carried.0 == *victim)
+            .map(|(_, _, armor, _)| armor.map_or(0, |a| a.0))
             .sum();
 
-        let final_damage = base_damage + weapon_damage;
+        // Armor can't turn an attack into healing.
+        let final_damage = i32::max(
+            0,
+            base_damage + strength_bonus + weapon_damage - base_armor - equipped_armor,
+        );
+
+        // Effects are inflicted only when the attack gets through the armor.
+        if final_damage > 0 {
+            if let (Ok(inflicts), Ok(mut status_effects)) = (
+                inflicts_query.get(*attacker),
+                status_effects_query.get_mut(*victim),
+            ) {
+                for effect in inflicts.0.iter() {
+                    status_effects.add(*effect);
+
+                    if is_player {
+                        game_log.add(
+                            format!("You are {}!", effect.kind.name().to_lowercase()),
+                            MAGENTA,
+                        );
+                    }
+                }
+            }
+        }
 
         if let Ok(mut health) = health_query.get_mut(*victim) {
+            // A victim may be attacked multiple times in the same turn; it's killed (and rewards the
+            // killer) only once.
+            let was_alive = health.current > 0;
             health.current -= final_damage;
-            if health.current < 1 && !is_player {
-                commands.entity(*victim).despawn();
+
+            if is_player {
+                if final_damage > 0 {
+                    game_log.add(
+                        format!("The {} hits you for {}.", attacker_name, final_damage),
+                        RED,
+                    );
+                } else {
+                    game_log.add(format!("The {} fails to hurt you.", attacker_name), GRAY);
+                }
+            } else if player_query.get(*attacker).is_ok() && was_alive {
+                if health.current < 1 {
+                    game_log.add(format!("You kill the {}.", victim_name), ORANGE);
+                } else if final_damage > 0 {
+                    game_log.add(
+                        format!("You hit the {} for {}.", victim_name, final_damage),
+                        YELLOW,
+                    );
+                } else {
+                    game_log.add(format!("You fail to hurt the {}.", victim_name), GRAY);
+                }
+            }
+
+            // The player's death is handled by end_turn.
+            if health.current < 1 && is_player && was_alive {
+                commands.insert_resource(DeathCause::Monster(attacker_name.clone()));
+            }
+
+            if health.current < 1 && !is_player && was_alive {
+                kill(
+                    &mut commands,
+                    *victim,
+                    *attacker,
+                    &mut experience_query,
+                    &experience_value_query,
+                );
+
+                if player_query.get(*attacker).is_ok() {
+                    run_stats.add_kill(&victim_name);
+                }
             }
         }
     }
 }
+
+// Shared by all the ways of killing a monster (attacks, items, traps): the victim is despawned, and the
+// killer gains the victim's experience value. Recording the player's kills is left to the caller.
+//
+pub(super) fn kill(
+    commands: &mut Commands,
+    victim: Entity,
+    killer: Entity,
+    experience_query: &mut Query<&mut Experience>,
+    experience_value_query: &Query<&ExperienceValue>,
+) {
+    commands.entity(victim).despawn();
+
+    if let (Ok(mut experience), Ok(value)) = (
+        experience_query.get_mut(killer),
+        experience_value_query.get(victim),
+    ) {
+        experience.xp += value.0;
+    }
+}
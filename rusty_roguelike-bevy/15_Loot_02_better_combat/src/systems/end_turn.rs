@@ -1,35 +1,196 @@
+use super::combat::kill;
+use crate::components::Name;
 use crate::prelude::*;
 
+const POISON_DAMAGE: i32 = 1;
+const REGENERATION_HEALING: i32 = 1;
+const STARVATION_DAMAGE: i32 = 1;
+
+// Safety net against actors that never gain energy (e.g. with a misconfigured speed).
+const MAX_ROUNDS_PER_TURN: usize = 100;
+
 pub fn end_turn(
     mut commands: Commands,
-    player_query: Query<(&Health, &PointC), With<Player>>,
+    mut player_query: Query<(Entity, &PointC, &mut Hunger), With<Player>>,
+    mut creatures_query: Query<(
+        Entity,
+        &mut Health,
+        &mut StatusEffects,
+        &Speed,
+        &mut Energy,
+        Option<&Acting>,
+    )>,
     amulet_query: Query<&PointC, With<AmuletOfYala>>,
+    mut fuel_query: Query<(Entity, &Carried, &mut Fuel), With<Equipped>>,
+    names_query: Query<&Name>,
+    mut experience_query: Query<&mut Experience>,
+    experience_value_query: Query<&ExperienceValue>,
     turn_state: Res<TurnState>,
-    map: Res<Map>,
+    mut run_stats: ResMut<RunStats>,
+    mut game_log: ResMut<GameLog>,
 ) {
-    let (player_hp, player_pos) = player_query.single();
-    let mut new_state = match *turn_state {
-        TurnState::PlayerTurn => TurnState::MonsterTurn,
-        TurnState::MonsterTurn => TurnState::AwaitingInput,
-        // In the source project, AwaitingInput and GameOver return (themselves), however, they're actually
-        // unreachable cases, because this system is not run in such states, and the change to their next
-        // states is performed elsewhere.
-        _ => unreachable!(),
-    };
+    let (player_entity, player_pos, mut hunger) = player_query.single_mut();
+
+    // The actors of the turn just finished spend their energy.
+    //
+    for (entity, _, _, _, mut energy, acting) in creatures_query.iter_mut() {
+        let acted = match *turn_state {
+            TurnState::PlayerTurn => entity == player_entity,
+            TurnState::MonsterTurn => acting.is_some(),
+            // In the source project, AwaitingInput and GameOver return (themselves), however, they're actually
+            // unreachable cases, because this system is not run in such states, and the change to their next
+            // states is performed elsewhere.
+            _ => unreachable!(),
+        };
+        if acted {
+            energy.0 -= ACTION_COST;
+        }
+        if acting.is_some() {
+            commands.entity(entity).remove::<Acting>();
+        }
+    }
+
+    // The player gets hungrier on each of their turns (which are also counted, for the morgue file); once
+    // starving, they lose health instead.
+    //
+    if *turn_state == TurnState::PlayerTurn {
+        run_stats.turns += 1;
+
+        let previous_state = hunger.state();
+        hunger.food = i32::max(0, hunger.food - 1);
+
+        if hunger.state() != previous_state {
+            match hunger.state() {
+                HungerState::Normal => game_log.add("You are no longer well fed.", GRAY),
+                HungerState::Hungry => game_log.add("You are getting hungry.", ORANGE),
+                HungerState::Starving => game_log.add("You are starving!", RED),
+                HungerState::WellFed => {}
+            }
+        }
+
+        if hunger.state() == HungerState::Starving {
+            let (_, mut health, _, _, _, _) = creatures_query.get_mut(player_entity).unwrap();
+            let was_alive = health.current > 0;
+            health.current -= STARVATION_DAMAGE;
+
+            if was_alive && health.current < 1 {
+                commands.insert_resource(DeathCause::Starvation);
+            }
+        }
+    }
+
+    // The next actors are the ones with enough energy; after each player action, the ready monsters act
+    // (so that, for example, a hasted player acts twice between the monster turns). When nobody is
+    // ready, a new round starts: all the actors gain energy according to their speed, and the status
+    // effects (which last a number of rounds) are applied.
+    //
+    let mut new_state = TurnState::AwaitingInput;
+
+    for _ in 0..MAX_ROUNDS_PER_TURN {
+        let mut monsters_ready = false;
+        for (entity, health, _, _, energy, _) in creatures_query.iter_mut() {
+            if entity != player_entity && health.current > 0 && energy.0 >= ACTION_COST {
+                commands.entity(entity).insert(Acting);
+                monsters_ready = true;
+            }
+        }
+
+        let (_, player_health, _, _, player_energy, _) =
+            creatures_query.get(player_entity).unwrap();
+
+        if monsters_ready && *turn_state == TurnState::PlayerTurn {
+            new_state = TurnState::MonsterTurn;
+            break;
+        } else if player_energy.0 >= ACTION_COST || player_health.current < 1 {
+            new_state = TurnState::AwaitingInput;
+            break;
+        } else if monsters_ready {
+            new_state = TurnState::MonsterTurn;
+            break;
+        }
+
+        for (entity, mut health, mut status_effects, speed, mut energy, _) in
+            creatures_query.iter_mut()
+        {
+            if health.current < 1 {
+                continue;
+            }
+
+            for effect in status_effects.0.iter() {
+                match effect.kind {
+                    StatusEffectKind::Poison => health.current -= POISON_DAMAGE,
+                    StatusEffectKind::Regeneration => {
+                        health.current = i32::min(health.max, health.current + REGENERATION_HEALING)
+                    }
+                    // The other effects are applied by the systems they affect.
+                    _ => {}
+                }
+            }
+            energy.0 += speed.effective(&status_effects);
+            status_effects.tick();
+
+            // The source of the poison is not tracked, so, as with the traps, the kills are credited to the
+            // player.
+            //
+            if health.current < 1 && entity == player_entity {
+                commands.insert_resource(DeathCause::Poison);
+            } else if health.current < 1 {
+                kill(
+                    &mut commands,
+                    entity,
+                    player_entity,
+                    &mut experience_query,
+                    &experience_value_query,
+                );
+
+                let name = entity_name(entity, &names_query);
+                run_stats.add_kill(&name);
+                game_log.add(format!("The {} succumbs to the poison.", name), ORANGE);
+            }
+        }
+
+        // The equipped light sources burn their fuel; the burnt out ones are despawned at the end of the
+        // stage, so they're skipped in the following rounds.
+        //
+        for (light, carried, mut fuel) in fuel_query.iter_mut() {
+            if fuel.0 <= 0 {
+                continue;
+            }
+            fuel.0 -= 1;
+
+            let light_name = entity_name(light, &names_query);
+            let carried_by_player = carried.0 == player_entity;
+
+            if fuel.0 <= 0 {
+                commands.entity(light).despawn();
+                if carried_by_player {
+                    game_log.add(format!("Your {} burns out.", light_name), ORANGE);
+                }
+            } else if fuel.0 == LOW_FUEL - 1 && carried_by_player {
+                game_log.add(format!("Your {} flickers.", light_name), YELLOW);
+            }
+        }
+    }
 
     let amulet_default = PointC(Point::new(-1, -1));
     let amulet_pos = amulet_query.get_single().unwrap_or(&amulet_default);
 
+    let (_, player_hp, _, _, _, _) = creatures_query.get(player_entity).unwrap();
+
+    let player_experience = experience_query.get(player_entity).unwrap();
+
+    // Leveling up is deferred to the player turn, so that the turn sequence is not interrupted.
+    if new_state == TurnState::AwaitingInput && player_experience.can_level_up() {
+        new_state = TurnState::LevelUp;
+    }
     if player_hp.current < 1 {
         new_state = TurnState::GameOver;
     }
     if player_pos.0 == amulet_pos.0 {
         new_state = TurnState::Victory;
     }
-    let idx = map.point2d_to_index(player_pos.0);
-    if map.tiles[idx] == TileType::Exit {
-        new_state = TurnState::NextLevel;
-    }
+    // Differently from the source project, changing level is an explicit player action (see player_input),
+    // since the player is placed on the stairs when entering a visited level.
 
     commands.insert_resource(new_state);
 }
@@ -2,18 +2,23 @@ use crate::prelude::*;
 
 pub fn entity_render(
     renderables: Query<(&PointC, &Render)>,
-    player_fov_query: Query<&FieldOfView, With<Player>>,
-    camera: Res<Camera>,
+    player_query: Query<(&PointC, &FieldOfView), With<Player>>,
+    (camera, light_map): (Res<Camera>, Res<LightMap>),
 ) {
     let mut draw_batch = DrawBatch::new();
     draw_batch.target(1);
     let offset = Point::new(camera.left_x, camera.top_y);
 
-    let player_fov = player_fov_query.single();
+    let (player_pos, player_fov) = player_query.single();
 
+    // Entities in the dark are not visible, even if they're in the field of view.
     for (pos, render) in renderables.iter() {
-        if player_fov.visible_tiles.contains(&pos.0) {
-            draw_batch.set(pos.0 - offset, render.color, render.glyph);
+        if light_map.can_see(player_pos.0, player_fov, pos.0) {
+            draw_batch.set(
+                pos.0 - offset,
+                light_map.shade(pos.0, render.color),
+                render.glyph,
+            );
         }
     }
 
@@ -0,0 +1,51 @@
+use super::ai::*;
+use crate::prelude::*;
+
+pub fn fleeing(
+    mut move_events: EventWriter<WantsToMove>,
+    mut attack_events: EventWriter<WantsToAttack>,
+    movers: Query<
+        (
+            Entity,
+            &PointC,
+            &FieldOfView,
+            &Health,
+            &FleesWhenHurt,
+            &StatusEffects,
+        ),
+        (With<Acting>, Without<Asleep>),
+    >,
+    positions: Query<(Entity, &PointC), With<Health>>,
+    player_query: Query<(Entity, &PointC), With<Player>>,
+    map: Res<Map>,
+) {
+    let (player_entity, player_pos) = player_query.single();
+    let dijkstra_map = dijkstra_map_to(player_pos.0, &map);
+
+    for (entity, pos, fov, health, flees_when_hurt, status_effects) in movers.iter() {
+        if !flees_when_hurt.is_fleeing(health)
+            || status_effects.has(StatusEffectKind::Confusion)
+            || !fov.visible_tiles.contains(&player_pos.0)
+        {
+            continue;
+        }
+
+        // A cornered monster fights back.
+        if let Some(destination) = step_away(pos.0, &dijkstra_map, &map) {
+            move_or_attack(
+                entity,
+                destination,
+                player_entity,
+                &positions,
+                &mut move_events,
+                &mut attack_events,
+            );
+        } else if DistanceAlg::Pythagoras.distance2d(pos.0, player_pos.0) <= MELEE_RANGE {
+            attack_events.send(WantsToAttack {
+                attacker: entity,
+                victim: player_entity,
+                ranged: false,
+            });
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use super::ai::*;
+use crate::prelude::*;
+
+pub fn guarding(
+    mut move_events: EventWriter<WantsToMove>,
+    mut attack_events: EventWriter<WantsToAttack>,
+    movers: Query<
+        (
+            Entity,
+            &PointC,
+            &FieldOfView,
+            &Health,
+            &Guarding,
+            Option<&FleesWhenHurt>,
+            &StatusEffects,
+        ),
+        (With<Acting>, Without<Asleep>),
+    >,
+    positions: Query<(Entity, &PointC), With<Health>>,
+    player_query: Query<(Entity, &PointC), With<Player>>,
+    map: Res<Map>,
+) {
+    let (player_entity, player_pos) = player_query.single();
+    let player_dijkstra_map = dijkstra_map_to(player_pos.0, &map);
+
+    for (entity, pos, fov, health, guarding, flees_when_hurt, status_effects) in movers.iter() {
+        if is_overridden(status_effects, flees_when_hurt, health) {
+            continue;
+        }
+
+        let intruder = fov.visible_tiles.contains(&player_pos.0)
+            && DistanceAlg::Pythagoras.distance2d(guarding.post, player_pos.0)
+                <= guarding.radius as f32;
+
+        let destination = if intruder {
+            step_towards(pos.0, player_pos.0, &player_dijkstra_map, &map)
+        } else if pos.0 != guarding.post {
+            let post_dijkstra_map = dijkstra_map_to(guarding.post, &map);
+            step_towards(pos.0, guarding.post, &post_dijkstra_map, &map)
+        } else {
+            None
+        };
+
+        if let Some(destination) = destination {
+            move_or_attack(
+                entity,
+                destination,
+                player_entity,
+                &positions,
+                &mut move_events,
+                &mut attack_events,
+            );
+        }
+    }
+}
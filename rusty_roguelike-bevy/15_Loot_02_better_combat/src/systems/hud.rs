@@ -1,15 +1,28 @@
-use crate::components::Name;
 use crate::prelude::*;
 
+const LOG_LINES: usize = 6;
+
 pub fn hud(
-    player_query: Query<(Entity, &Player, &Health)>,
-    item_query: Query<(&Name, &Carried), With<Item>>,
+    player_query: Query<(
+        Entity,
+        &Player,
+        &Health,
+        &StatusEffects,
+        &Experience,
+        &Hunger,
+    )>,
+    carried_items_query: CarriedItemsQuery,
+    inventory_screen: Res<InventoryScreen>,
+    identification: Res<Identification>,
+    key_bindings: Res<KeyBindings>,
+    targeting: Option<Res<Targeting>>,
+    game_log: Res<GameLog>,
 ) {
-    let (player_entity, player, player_health) = player_query.single();
+    let (player_entity, player, player_health, status_effects, experience, hunger) =
+        player_query.single();
 
     let mut draw_batch = DrawBatch::new();
     draw_batch.target(2);
-    draw_batch.print_centered(1, "Explore the Dungeon. Cursor keys to move.");
     draw_batch.bar_horizontal(
         Point::zero(),
         SCREEN_WIDTH * 2,
@@ -36,14 +49,70 @@ pub fn hud(
         ColorPair::new(YELLOW, BLACK),
     );
 
-    let mut y = 3;
-    for (name, carried) in item_query.iter() {
-        if carried.0 == player_entity {
-            draw_batch.print(Point::new(3, y), format!("{} : {}", y - 2, &name.0));
-            y += 1;
-        };
+    if targeting.is_some() {
+        draw_batch.print_color_centered(
+            3,
+            "Select a target: cursor keys/mouse to aim, Tab to cycle, Enter/F/click to confirm, Escape to cancel.",
+            ColorPair::new(GREEN, BLACK),
+        );
     }
-    if y > 3 {
+
+    draw_batch.print_color(
+        Point::new(0, 1),
+        format!(
+            "Level: {} XP: {} / {}",
+            experience.level,
+            experience.xp,
+            experience.next_level_xp()
+        ),
+        ColorPair::new(YELLOW, BLACK),
+    );
+
+    let effects_line = status_effects
+        .0
+        .iter()
+        .map(|effect| format!("{} ({})", effect.kind.name(), effect.turns))
+        .collect::<Vec<_>>()
+        .join(", ");
+    draw_batch.print_color_right(
+        Point::new(SCREEN_WIDTH * 2, 2),
+        effects_line,
+        ColorPair::new(CYAN, BLACK),
+    );
+
+    let hunger_color = match hunger.state() {
+        HungerState::WellFed => GREEN,
+        HungerState::Normal => WHITE,
+        HungerState::Hungry => ORANGE,
+        HungerState::Starving => RED,
+    };
+    draw_batch.print_color_right(
+        Point::new(SCREEN_WIDTH * 2, 3),
+        hunger.state().name(),
+        ColorPair::new(hunger_color, BLACK),
+    );
+
+    // Only the items selectable via the number keys are listed.
+    let entries = carried_items(
+        player_entity,
+        &carried_items_query,
+        &identification,
+        inventory_screen.sort,
+    );
+    for (i, entry) in entries.iter().take(9).enumerate() {
+        draw_batch.print(
+            Point::new(3, 3 + i as i32),
+            format!("{} : {}", i + 1, entry.display_text()),
+        );
+    }
+    if entries.len() > 9 {
+        draw_batch.print_color(
+            Point::new(3, 12),
+            format!("... and {} more", entries.len() - 9),
+            ColorPair::new(GRAY, BLACK),
+        );
+    }
+    if !entries.is_empty() {
         draw_batch.print_color(
             Point::new(3, 2),
             "Items carried",
@@ -51,5 +120,47 @@ pub fn hud(
         );
     }
 
+    // The help has its own row, above the log, since it spans almost the whole screen width.
+    draw_batch.print_centered(
+        SCREEN_HEIGHT * 2 - LOG_LINES as i32 - 1,
+        help_text(&key_bindings),
+    );
+
+    // The most recent messages are shown at the bottom of the screen.
+    let log_entries = game_log.last_entries(LOG_LINES);
+    for (i, entry) in log_entries.iter().enumerate() {
+        draw_batch.print_color(
+            Point::new(1, SCREEN_HEIGHT * 2 - log_entries.len() as i32 + i as i32),
+            entry.display_text(),
+            ColorPair::new(entry.color, BLACK),
+        );
+    }
+
     draw_batch.submit(10000).expect("Batch error");
 }
+
+// The keys are taken from the bindings, since they're configurable; unbound actions are omitted.
+//
+fn help_text(key_bindings: &KeyBindings) -> String {
+    let actions = [
+        (Action::Wait, "rest"),
+        (Action::Explore, "explore"),
+        (Action::Fire, "fire"),
+        (Action::Search, "search"),
+        (Action::CloseDoor, "close door"),
+        (Action::Inventory, "inventory"),
+        (Action::MessageLog, "log"),
+        (Action::Descend, "descend"),
+        (Action::Ascend, "ascend"),
+        (Action::SaveAndQuit, "save and quit"),
+    ];
+
+    let mut entries = vec!["Cursor keys/click: move".to_string()];
+    for (action, description) in actions {
+        if let Some(key_name) = key_bindings.key_name(action) {
+            entries.push(format!("{}: {}", key_name, description));
+        }
+    }
+
+    entries.join(", ") + "."
+}
@@ -0,0 +1,231 @@
+use crate::prelude::*;
+
+const LIST_X: i32 = 2;
+const DETAILS_X: i32 = SCREEN_WIDTH;
+const DETAILS_WIDTH: usize = (SCREEN_WIDTH - 2) as usize;
+// Leaves room for the title and the instructions.
+const LIST_HEIGHT: usize = (SCREEN_HEIGHT * 2 - 8) as usize;
+
+pub fn inventory(
+    mut commands: Commands,
+    mut active_item_events: EventWriter<ActivateItem>,
+    player_query: Query<(Entity, &PointC), With<Player>>,
+    carried_items_query: CarriedItemsQuery,
+    details_query: Query<(
+        Option<&Description>,
+        Option<&Damage>,
+        Option<&Armor>,
+        Option<&Ranged>,
+        Option<&AreaOfEffect>,
+        Option<&ProvidesHealing>,
+        Option<&ProvidesFood>,
+        Option<&ProvidesStatusEffects>,
+        Option<&LightSource>,
+        Option<&Fuel>,
+    )>,
+    key: Option<Res<VirtualKeyCode>>,
+    mut inventory_screen: ResMut<InventoryScreen>,
+    identification: Res<Identification>,
+    mut game_log: ResMut<GameLog>,
+) {
+    let (player_entity, player_pos) = player_query.single();
+
+    let entries = carried_items(
+        player_entity,
+        &carried_items_query,
+        &identification,
+        inventory_screen.sort,
+    );
+    let selected_entry = entries.get(inventory_screen.selected);
+
+    if let Some(key) = key.as_deref() {
+        match key {
+            VirtualKeyCode::Up => {
+                inventory_screen.selected = inventory_screen.selected.saturating_sub(1)
+            }
+            VirtualKeyCode::Down => inventory_screen.selected += 1,
+            VirtualKeyCode::S => inventory_screen.sort = inventory_screen.sort.next(),
+            VirtualKeyCode::Return | VirtualKeyCode::U => {
+                if let Some(entry) = selected_entry {
+                    let item = entry.item();
+                    let (_, _, _, ranged, area_of_effect, _, _, _, _, _) =
+                        details_query.get(item).unwrap();
+
+                    // Same as player_input; equippable ranged items (weapons) are equipped, not fired.
+                    match ranged {
+                        Some(ranged) if entry.slot.is_none() => {
+                            commands.insert_resource(Targeting::for_item(
+                                item,
+                                ranged,
+                                area_of_effect,
+                                player_pos.0,
+                            ));
+                            commands.insert_resource(TurnState::SelectingTarget);
+                        }
+                        _ => {
+                            active_item_events.send(ActivateItem {
+                                used_by: player_entity,
+                                item,
+                                target: None,
+                            });
+                            commands.insert_resource(TurnState::PlayerTurn);
+                        }
+                    }
+                }
+            }
+            // Dropping takes a turn, like picking up.
+            VirtualKeyCode::D => {
+                if let Some(entry) = selected_entry {
+                    commands
+                        .entity(entry.item())
+                        .remove::<Carried>()
+                        .remove::<Equipped>()
+                        .insert(PointC(player_pos.0));
+                    game_log.add(format!("You drop the {}.", entry.name), CYAN);
+                    commands.insert_resource(TurnState::PlayerTurn);
+                }
+            }
+            VirtualKeyCode::I | VirtualKeyCode::Escape => {
+                commands.insert_resource(TurnState::AwaitingInput);
+            }
+            _ => {}
+        }
+
+        // See player_input.
+        commands.remove_resource::<VirtualKeyCode>();
+    }
+
+    // The list may have shrunk (e.g. after using the last item of a stack).
+    inventory_screen.selected = inventory_screen
+        .selected
+        .min(entries.len().saturating_sub(1));
+    let selected = inventory_screen.selected;
+
+    let mut draw_batch = DrawBatch::new();
+    draw_batch.target(2);
+    draw_batch.print_color_centered(
+        1,
+        format!("Inventory (sorted by {})", inventory_screen.sort.name()),
+        ColorPair::new(YELLOW, BLACK),
+    );
+
+    if entries.is_empty() {
+        draw_batch.print(Point::new(LIST_X, 3), "You're not carrying anything.");
+    }
+
+    // The list scrolls, so that the selected entry is always visible.
+    let first_shown = (selected + 1).saturating_sub(LIST_HEIGHT);
+    for (i, entry) in entries
+        .iter()
+        .enumerate()
+        .skip(first_shown)
+        .take(LIST_HEIGHT)
+    {
+        let color = if i == selected {
+            ColorPair::new(BLACK, WHITE)
+        } else {
+            ColorPair::new(WHITE, BLACK)
+        };
+        draw_batch.print_color(
+            Point::new(LIST_X, 3 + (i - first_shown) as i32),
+            entry.display_text(),
+            color,
+        );
+    }
+
+    if let Some(entry) = entries.get(selected) {
+        let (
+            description,
+            damage,
+            armor,
+            ranged,
+            area_of_effect,
+            healing,
+            food,
+            status_effects,
+            light_source,
+            fuel,
+        ) = details_query.get(entry.item()).unwrap();
+
+        let mut lines = Vec::new();
+        if let Some(description) = description {
+            lines.extend(wrap_text(&description.0, DETAILS_WIDTH));
+            lines.push(String::new());
+        }
+        if let Some(slot) = entry.slot {
+            lines.push(format!("Slot: {:?}", slot));
+        }
+        if let Some(damage) = damage {
+            lines.push(format!("Damage: {}", damage.0));
+        }
+        if let Some(armor) = armor {
+            lines.push(format!("Armor: {}", armor.0));
+        }
+        if let Some(ranged) = ranged {
+            lines.push(format!("Range: {}", ranged.range));
+        }
+        if let Some(area_of_effect) = area_of_effect {
+            lines.push(format!("Area of effect: {}", area_of_effect.radius));
+        }
+        if let Some(healing) = healing {
+            lines.push(format!("Heals: {}", healing.amount));
+        }
+        if let Some(food) = food {
+            lines.push(format!("Nourishment: {}", food.amount));
+        }
+        if let Some(light_source) = light_source {
+            lines.push(format!("Light radius: {}", light_source.radius));
+        }
+        if let Some(fuel) = fuel {
+            lines.push(format!("Fuel: {} turns", fuel.0));
+        }
+        if let Some(status_effects) = status_effects {
+            for effect in status_effects.0.iter() {
+                lines.push(format!("{} ({} turns)", effect.kind.name(), effect.turns));
+            }
+        }
+        // The properties of unidentified items are unknown, until they're used (or identified).
+        if !entry.identified {
+            lines = vec!["You don't know what this item does.".to_string()];
+        }
+
+        draw_batch.print_color(
+            Point::new(DETAILS_X, 3),
+            &entry.name,
+            ColorPair::new(YELLOW, BLACK),
+        );
+        for (i, line) in lines.iter().enumerate() {
+            draw_batch.print(Point::new(DETAILS_X, 5 + i as i32), line);
+        }
+    }
+
+    draw_batch.print_color_centered(
+        SCREEN_HEIGHT * 2 - 2,
+        "Up/Down to select, Enter to use, D to drop, S to change the sorting, I or Escape to close.",
+        ColorPair::new(GREEN, BLACK),
+    );
+
+    draw_batch.submit(10000).expect("Batch error");
+}
+
+// Splits the text into lines of at most `width` characters, at word boundaries.
+//
+fn wrap_text(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.len() + 1 + word.len() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+
+    lines
+}
@@ -0,0 +1,65 @@
+use super::ai::*;
+use crate::prelude::*;
+
+pub fn kiting(
+    mut move_events: EventWriter<WantsToMove>,
+    mut attack_events: EventWriter<WantsToAttack>,
+    movers: Query<
+        (
+            Entity,
+            &PointC,
+            &FieldOfView,
+            &Health,
+            &Kiting,
+            Option<&FleesWhenHurt>,
+            &StatusEffects,
+        ),
+        (With<Acting>, Without<Asleep>),
+    >,
+    positions: Query<(Entity, &PointC), With<Health>>,
+    player_query: Query<(Entity, &PointC), With<Player>>,
+    map: Res<Map>,
+) {
+    let (player_entity, player_pos) = player_query.single();
+    let dijkstra_map = dijkstra_map_to(player_pos.0, &map);
+
+    for (entity, pos, fov, health, kiting, flees_when_hurt, status_effects) in movers.iter() {
+        if is_overridden(status_effects, flees_when_hurt, health)
+            || !fov.visible_tiles.contains(&player_pos.0)
+        {
+            continue;
+        }
+
+        let distance = DistanceAlg::Pythagoras.distance2d(pos.0, player_pos.0);
+
+        // When adjacent, the monster backs off, if possible; it fights in melee otherwise. Since the
+        // player is visible, there's a line of sight, so ranged attacks don't need further checks.
+        //
+        let destination = if distance <= MELEE_RANGE {
+            step_away(pos.0, &dijkstra_map, &map).unwrap_or(player_pos.0)
+        } else if distance <= kiting.range as f32 {
+            player_pos.0
+        } else if let Some(destination) = step_towards(pos.0, player_pos.0, &dijkstra_map, &map) {
+            destination
+        } else {
+            continue;
+        };
+
+        if destination == player_pos.0 {
+            attack_events.send(WantsToAttack {
+                attacker: entity,
+                victim: player_entity,
+                ranged: distance > MELEE_RANGE,
+            });
+        } else {
+            move_or_attack(
+                entity,
+                destination,
+                player_entity,
+                &positions,
+                &mut move_events,
+                &mut attack_events,
+            );
+        }
+    }
+}
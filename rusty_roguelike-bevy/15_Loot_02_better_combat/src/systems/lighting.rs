@@ -0,0 +1,24 @@
+use crate::prelude::*;
+
+// Light sources on the map (e.g. wall torches, dropped lanterns) light their position; equipped ones
+// light their carrier's.
+//
+pub fn lighting(
+    lights_query: Query<(&PointC, &LightSource, Option<&Fuel>)>,
+    carried_lights_query: Query<(&Carried, &LightSource, Option<&Fuel>), With<Equipped>>,
+    positions_query: Query<&PointC>,
+    map: Res<Map>,
+    mut light_map: ResMut<LightMap>,
+) {
+    light_map.reset(map.ambient_light);
+
+    for (pos, light, fuel) in lights_query.iter() {
+        light_map.add_light(pos.0, light.effective_radius(fuel), &map);
+    }
+
+    for (carried, light, fuel) in carried_lights_query.iter() {
+        if let Ok(pos) = positions_query.get(carried.0) {
+            light_map.add_light(pos.0, light.effective_radius(fuel), &map);
+        }
+    }
+}
@@ -2,7 +2,7 @@ use crate::prelude::*;
 
 pub fn map_render(
     player_fov_query: Query<&FieldOfView, With<Player>>,
-    (map, camera, theme): (Res<Map>, Res<Camera>, Res<Box<dyn MapTheme>>),
+    (map, camera, theme, light_map): (Res<Map>, Res<Camera>, Res<Box<dyn MapTheme>>, Res<LightMap>),
 ) {
     let mut draw_batch = DrawBatch::new();
     draw_batch.target(0);
@@ -19,14 +19,17 @@ pub fn map_render(
             if map.in_bounds(pt)
                 && (player_fov.visible_tiles.contains(&pt) || map.revealed_tiles[idx])
             {
-                let tint = if player_fov.visible_tiles.contains(&pt) {
-                    WHITE
+                let (glyph, colors) = theme.tile_to_render(map.tiles[idx], map.variants[idx]);
+
+                // Visible tiles are shaded according to their light; remembered tiles are shown without
+                // the theme colors.
+                let colors = if player_fov.visible_tiles.contains(&pt) {
+                    light_map.shade(pt, colors)
                 } else {
-                    DARK_GRAY
+                    ColorPair::new(DARK_GRAY, BLACK)
                 };
 
-                let glyph = theme.tile_to_render(map.tiles[idx]);
-                draw_batch.set(pt - offset, ColorPair::new(tint, BLACK), glyph);
+                draw_batch.set(pt - offset, colors, glyph);
             }
         }
     }
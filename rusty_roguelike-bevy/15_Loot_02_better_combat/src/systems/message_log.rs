@@ -0,0 +1,56 @@
+use crate::prelude::*;
+
+// Number of entries fitting the screen, leaving room for the title and the instructions.
+const PAGE_SIZE: usize = (SCREEN_HEIGHT * 2 - 8) as usize;
+
+pub fn message_log(
+    mut commands: Commands,
+    mut game_log: ResMut<GameLog>,
+    key: Option<Res<VirtualKeyCode>>,
+) {
+    let max_offset = game_log.entries.len().saturating_sub(PAGE_SIZE);
+
+    if let Some(key) = key.as_deref() {
+        match key {
+            VirtualKeyCode::Up => game_log.scroll_offset += 1,
+            VirtualKeyCode::Down => {
+                game_log.scroll_offset = game_log.scroll_offset.saturating_sub(1)
+            }
+            VirtualKeyCode::PageUp => game_log.scroll_offset += PAGE_SIZE,
+            VirtualKeyCode::PageDown => {
+                game_log.scroll_offset = game_log.scroll_offset.saturating_sub(PAGE_SIZE)
+            }
+            VirtualKeyCode::M | VirtualKeyCode::Escape => {
+                game_log.scroll_offset = 0;
+                commands.insert_resource(TurnState::AwaitingInput);
+            }
+            _ => {}
+        }
+        game_log.scroll_offset = game_log.scroll_offset.min(max_offset);
+
+        // See player_input.
+        commands.remove_resource::<VirtualKeyCode>();
+    }
+
+    let mut draw_batch = DrawBatch::new();
+    draw_batch.target(2);
+    draw_batch.print_color_centered(1, "Message Log", ColorPair::new(YELLOW, BLACK));
+
+    let end = game_log.entries.len() - game_log.scroll_offset;
+    let start = end.saturating_sub(PAGE_SIZE);
+    for (i, entry) in game_log.entries[start..end].iter().enumerate() {
+        draw_batch.print_color(
+            Point::new(2, 3 + i as i32),
+            entry.display_text(),
+            ColorPair::new(entry.color, BLACK),
+        );
+    }
+
+    draw_batch.print_color_centered(
+        SCREEN_HEIGHT * 2 - 2,
+        "Up/Down/PageUp/PageDown to scroll, M or Escape to close.",
+        ColorPair::new(GREEN, BLACK),
+    );
+
+    draw_batch.submit(10000).expect("Batch error");
+}
@@ -1,17 +1,30 @@
 use crate::prelude::*;
 
+mod ai;
+mod auto_move;
 mod chasing;
 mod combat;
 mod end_turn;
 mod entity_render;
+mod fleeing;
 mod fov;
+mod guarding;
 mod hud;
+mod inventory;
+mod kiting;
+mod lighting;
 mod map_render;
+mod message_log;
 mod movement;
+mod pack;
+mod patrolling;
 mod player_input;
 mod random_move;
+mod targeting;
+mod targeting_render;
 mod tooltips;
 mod use_items;
+mod wake_up;
 
 pub fn build_system_sets(app: &mut App) {
     use GameStage::*;
@@ -37,8 +50,8 @@ pub fn build_system_sets(app: &mut App) {
     //
     // - instead of using a machine state that swaps schedulers (see the source project's `State#tick`),
     //   we use filters on systems/sets
-    // - the fov needs to be performed in the first stage, before map/entity rendering, due to the source
-    //   design
+    // - the fov (along with the lighting) needs to be performed in the first stage, before map/entity
+    //   rendering, due to the source design
     // - rendering is performed in the first stage (of each frame, except in GameOver state); it does
     //   not make a difference from the user perspective, but it's clear from a design one; this is
     //   possible due to the single scheduler model
@@ -50,12 +63,16 @@ pub fn build_system_sets(app: &mut App) {
             .label(StateLabel::Fov)
             .run_unless_resource_equals(GameOver)
             .with_system(fov::fov)
+            .with_system(lighting::lighting)
             .into(),
     );
 
     app.add_system_set(
         ConditionSet::new()
             .run_unless_resource_equals(GameOver)
+            .run_unless_resource_equals(LevelUp)
+            .run_unless_resource_equals(ShowingLog)
+            .run_unless_resource_equals(ShowingInventory)
             .after(StateLabel::Fov)
             .with_system(map_render::map_render)
             .with_system(entity_render::entity_render)
@@ -63,6 +80,7 @@ pub fn build_system_sets(app: &mut App) {
             // In the source project, the tooltips system is run only in the player input frames.
             // Here, due to the different design, it's executed on every frame.
             .with_system(tooltips::tooltips)
+            .with_system(targeting_render::targeting_render)
             .into(),
     );
 
@@ -70,6 +88,28 @@ pub fn build_system_sets(app: &mut App) {
         ConditionSet::new()
             .run_if_resource_equals(AwaitingInput)
             .with_system(player_input::player_input)
+            .with_system(auto_move::auto_move)
+            .into(),
+    );
+
+    app.add_system_set(
+        ConditionSet::new()
+            .run_if_resource_equals(ShowingLog)
+            .with_system(message_log::message_log)
+            .into(),
+    );
+
+    app.add_system_set(
+        ConditionSet::new()
+            .run_if_resource_equals(ShowingInventory)
+            .with_system(inventory::inventory)
+            .into(),
+    );
+
+    app.add_system_set(
+        ConditionSet::new()
+            .run_if_resource_equals(SelectingTarget)
+            .with_system(targeting::targeting)
             .into(),
     );
 
@@ -85,8 +125,17 @@ pub fn build_system_sets(app: &mut App) {
     app.add_system_set_to_stage(
         MovePlayer,
         ConditionSet::new()
+            .label(StateLabel::Movement)
             .run_if_resource_equals(PlayerTurn)
             .with_system(movement::movement)
+            .into(),
+    );
+
+    app.add_system_set_to_stage(
+        MovePlayer,
+        ConditionSet::new()
+            .after(StateLabel::Movement)
+            .run_if_resource_equals(PlayerTurn)
             .with_system(end_turn::end_turn)
             .into(),
     );
@@ -102,9 +151,39 @@ pub fn build_system_sets(app: &mut App) {
     app.add_system_set_to_stage(
         GenerateMonsterMoves,
         ConditionSet::new()
+            .label(StateLabel::RandomMoves)
             .run_if_resource_equals(MonsterTurn)
             .with_system(random_move::random_move)
+            .into(),
+    );
+
+    app.add_system_set_to_stage(
+        GenerateMonsterMoves,
+        ConditionSet::new()
+            .label(StateLabel::MonsterMoves)
+            .after(StateLabel::RandomMoves)
+            .run_if_resource_equals(MonsterTurn)
+            // Each monster behavior is a separate system; they don't overlap, since the behaviors
+            // with priority (confusion, fleeing) are excluded by the others, and the behavior
+            // components are excluded by the systems of the others (see e.g. patrolling). Only the
+            // monsters marked as Acting (see end_turn) take part in a monsters turn.
             .with_system(chasing::chasing)
+            .with_system(fleeing::fleeing)
+            .with_system(kiting::kiting)
+            .with_system(guarding::guarding)
+            .with_system(patrolling::patrolling)
+            .with_system(pack::pack)
+            .into(),
+    );
+
+    // The attacks generated by the behaviors wake up the monsters nearby.
+    //
+    app.add_system_set_to_stage(
+        GenerateMonsterMoves,
+        ConditionSet::new()
+            .after(StateLabel::MonsterMoves)
+            .run_if_resource_equals(MonsterTurn)
+            .with_system(wake_up::wake_up)
             .into(),
     );
 
@@ -120,8 +199,17 @@ pub fn build_system_sets(app: &mut App) {
     app.add_system_set_to_stage(
         MoveMonsters,
         ConditionSet::new()
+            .label(StateLabel::Movement)
             .run_if_resource_equals(MonsterTurn)
             .with_system(movement::movement)
+            .into(),
+    );
+
+    app.add_system_set_to_stage(
+        MoveMonsters,
+        ConditionSet::new()
+            .after(StateLabel::Movement)
+            .run_if_resource_equals(MonsterTurn)
             .with_system(end_turn::end_turn)
             .into(),
     );
@@ -1,31 +1,404 @@
+use super::combat::kill;
+use crate::components::Name;
 use crate::prelude::*;
+use std::collections::{HashMap, HashSet};
 
+const TRAP_DAMAGE: i32 = 3;
+
+// Besides moving the creatures, this handles the interactive tiles: walking into a door opens it
+// (unlocking it, if the player carries a key), the player breaks through cracked walls, and stepping on a
+// trap or pressure plate triggers it. Opening doors and breaking walls take the move.
+//
 pub fn movement(
     mut commands: Commands,
     mut move_events: EventReader<WantsToMove>,
     query: Query<(Entity, &FieldOfView, Option<&Player>)>,
-    (mut map, mut camera): (ResMut<Map>, ResMut<Camera>),
+    creatures_query: Query<(Entity, &PointC), With<Health>>,
+    mut health_query: Query<&mut Health>,
+    keys_query: Query<(Entity, &Carried), With<Key>>,
+    sleepers_query: Query<Entity, With<Asleep>>,
+    names_query: Query<&Name>,
+    (mut experience_query, experience_value_query): (
+        Query<&mut Experience>,
+        Query<&ExperienceValue>,
+    ),
+    (mut map, mut camera, mut game_log, mut run_stats, light_map): (
+        ResMut<Map>,
+        ResMut<Camera>,
+        ResMut<GameLog>,
+        ResMut<RunStats>,
+        Res<LightMap>,
+    ),
 ) {
-    for &WantsToMove {
+    let (player_entity, player_fov) = query
+        .iter()
+        .find_map(|(entity, fov, player)| player.map(|_| (entity, fov)))
+        .unwrap();
+
+    let mut intents = Vec::new();
+    let mut tiles_changed = false;
+
+    for WantsToMove {
         entity,
         destination,
     } in move_events.iter()
     {
-        if map.can_enter_tile(destination) {
-            commands.entity(entity).insert(PointC(destination));
-
-            if let Ok((entity, fov, player)) = query.get(entity) {
-                // In Bevy, we don't need to test for Result<FieldOfView>, because the entity, if found,
-                // will have a FieldOfView component, due to the query definition.
-                commands.entity(entity).insert(fov.clone_dirty());
-
-                if player.is_some() {
-                    camera.on_player_move(destination);
-                    fov.visible_tiles.iter().for_each(|pos| {
-                        map.revealed_tiles[map_idx(pos.x, pos.y)] = true;
-                    });
+        let idx = match map.try_idx(*destination) {
+            Some(idx) => idx,
+            None => continue,
+        };
+        let is_player = *entity == player_entity;
+
+        match map.tiles[idx] {
+            TileType::Door => {
+                map.tiles[idx] = TileType::OpenDoor;
+                tiles_changed = true;
+                if is_player {
+                    game_log.add("You open the door.", GRAY);
+                }
+            }
+            TileType::LockedDoor if is_player => {
+                if let Some((key, _)) = keys_query.iter().find(|(_, carried)| carried.0 == *entity)
+                {
+                    commands.entity(key).despawn();
+                    map.tiles[idx] = TileType::OpenDoor;
+                    tiles_changed = true;
+                    game_log.add("You unlock the door; the key stays in the lock.", CYAN);
+                } else {
+                    game_log.add("The door is locked.", GRAY);
+                }
+            }
+            TileType::CrackedWall if is_player => {
+                map.tiles[idx] = TileType::Floor;
+                tiles_changed = true;
+                game_log.add("You break through the cracked wall.", YELLOW);
+            }
+            _ if map.can_enter_tile(*destination) => intents.push((*entity, *destination)),
+            _ => {}
+        }
+    }
+
+    // What the creatures see may have changed.
+    if tiles_changed {
+        for (entity, fov, _) in query.iter() {
+            commands.entity(entity).insert(fov.clone_dirty());
+        }
+    }
+
+    let positions = creatures_query
+        .iter()
+        .map(|(entity, pos)| (entity, pos.0))
+        .collect::<HashMap<_, _>>();
+    let player_pos = positions[&player_entity];
+
+    for (entity, destination) in resolve_moves(&positions, intents) {
+        commands.entity(entity).insert(PointC(destination));
+
+        if let Ok((entity, fov, player)) = query.get(entity) {
+            // In Bevy, we don't need to test for Result<FieldOfView>, because the entity, if found,
+            // will have a FieldOfView component, due to the query definition.
+            commands.entity(entity).insert(fov.clone_dirty());
+
+            if player.is_some() {
+                camera.on_player_move(destination);
+                fov.visible_tiles.iter().for_each(|pos| {
+                    map.revealed_tiles[map_idx(pos.x, pos.y)] = true;
+                });
+            }
+        }
+
+        // Only what the player sees is logged.
+        //
+        let is_player = entity == player_entity;
+        let seen = is_player || light_map.can_see(player_pos, player_fov, destination);
+        let name = entity_name(entity, &names_query);
+        let idx = map.point2d_to_index(destination);
+
+        match map.tiles[idx] {
+            TileType::Trap | TileType::HiddenTrap => {
+                map.tiles[idx] = TileType::Trap;
+
+                if let Ok(mut health) = health_query.get_mut(entity) {
+                    let was_alive = health.current > 0;
+                    health.current -= TRAP_DAMAGE;
+
+                    if is_player {
+                        game_log.add(
+                            format!("You trigger a trap, and take {} damage.", TRAP_DAMAGE),
+                            RED,
+                        );
+                    } else if seen {
+                        game_log.add(format!("The {} triggers a trap.", name), YELLOW);
+                    }
+
+                    // The player's death is handled by end_turn.
+                    if health.current < 1 && is_player && was_alive {
+                        commands.insert_resource(DeathCause::Trap);
+                    }
+                    // Luring monsters into traps is a legitimate tactic, so the kills are credited to
+                    // the player.
+                    //
+                    if health.current < 1 && !is_player && was_alive {
+                        kill(
+                            &mut commands,
+                            entity,
+                            player_entity,
+                            &mut experience_query,
+                            &experience_value_query,
+                        );
+                        run_stats.add_kill(&name);
+                        if seen {
+                            game_log.add(format!("The {} is killed by the trap.", name), ORANGE);
+                        }
+                    }
                 }
             }
+            TileType::PressurePlate => {
+                for sleeper in sleepers_query.iter() {
+                    commands.entity(sleeper).remove::<Asleep>();
+                }
+                if seen {
+                    game_log.add(
+                        "A pressure plate clicks, and an alarm echoes through the dungeon!",
+                        ORANGE,
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// Since all the intents of a turn are generated before any of them is applied, they may conflict.
+// Destinations are reserved so that no two creatures end up on the same tile:
+//
+// - when multiple creatures want to move to the same tile, the one with the lowest entity id wins (the
+//   events order depends on the systems scheduling, so it can't be used);
+// - creatures that don't move (including the losers above) block their tile, which may in turn block
+//   other creatures, so this is repeated until there are no changes;
+// - the remaining moves are valid; this includes chains (a creature moving into a tile that is being
+//   vacated), and swaps/rotations (e.g. two monsters crossing each other in a corridor).
+//
+// Only the first intent of each entity is considered.
+//
+fn resolve_moves(
+    positions: &HashMap<Entity, Point>,
+    mut intents: Vec<(Entity, Point)>,
+) -> Vec<(Entity, Point)> {
+    let mut seen_entities = HashSet::new();
+    intents.retain(|(entity, _)| seen_entities.insert(*entity));
+    intents.sort_by_key(|(entity, _)| entity.id());
+
+    let mut reserved_destinations = HashSet::new();
+    intents.retain(|(_, destination)| reserved_destinations.insert(*destination));
+
+    loop {
+        let moving_entities = intents
+            .iter()
+            .map(|(entity, _)| *entity)
+            .collect::<HashSet<_>>();
+        let blocked_tiles = positions
+            .iter()
+            .filter(|(entity, _)| !moving_entities.contains(entity))
+            .map(|(_, pos)| *pos)
+            .collect::<HashSet<_>>();
+
+        let intents_count = intents.len();
+        intents.retain(|(_, destination)| !blocked_tiles.contains(destination));
+
+        if intents.len() == intents_count {
+            return intents;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::event::Events;
+
+    // Creatures are (id, position).
+    //
+    fn positions(creatures: &[(u32, (i32, i32))]) -> HashMap<Entity, Point> {
+        creatures
+            .iter()
+            .map(|(id, (x, y))| (Entity::from_raw(*id), Point::new(*x, *y)))
+            .collect()
+    }
+
+    fn resolve(
+        creatures: &[(u32, (i32, i32))],
+        intents: &[(u32, (i32, i32))],
+    ) -> Vec<(u32, Point)> {
+        let intents = intents
+            .iter()
+            .map(|(id, (x, y))| (Entity::from_raw(*id), Point::new(*x, *y)))
+            .collect();
+
+        resolve_moves(&positions(creatures), intents)
+            .into_iter()
+            .map(|(entity, destination)| (entity.id(), destination))
+            .collect()
+    }
+
+    #[test]
+    fn creatures_swap() {
+        let moves = resolve(&[(0, (1, 1)), (1, (2, 1))], &[(0, (2, 1)), (1, (1, 1))]);
+
+        assert_eq!(moves, vec![(0, Point::new(2, 1)), (1, Point::new(1, 1))]);
+    }
+
+    #[test]
+    fn creature_moves_into_vacated_tile() {
+        let moves = resolve(
+            &[(0, (1, 1)), (1, (2, 1)), (2, (3, 1))],
+            &[(0, (2, 1)), (1, (3, 1)), (2, (4, 1))],
+        );
+
+        assert_eq!(
+            moves,
+            vec![
+                (0, Point::new(2, 1)),
+                (1, Point::new(3, 1)),
+                (2, Point::new(4, 1))
+            ]
+        );
+    }
+
+    // The events order must not matter.
+    //
+    #[test]
+    fn lowest_id_wins_contested_tile() {
+        let creatures = [(0, (1, 1)), (1, (3, 1))];
+
+        let moves = resolve(&creatures, &[(1, (2, 1)), (0, (2, 1))]);
+        assert_eq!(moves, vec![(0, Point::new(2, 1))]);
+
+        let moves = resolve(&creatures, &[(0, (2, 1)), (1, (2, 1))]);
+        assert_eq!(moves, vec![(0, Point::new(2, 1))]);
+    }
+
+    #[test]
+    fn stationary_creature_blocks_move() {
+        let moves = resolve(&[(0, (1, 1)), (1, (2, 1))], &[(0, (2, 1))]);
+
+        assert!(moves.is_empty());
+    }
+
+    // The loser of a contested tile doesn't vacate its own, so the creature moving into it is blocked.
+    //
+    #[test]
+    fn blocked_creature_blocks_chain() {
+        let moves = resolve(
+            &[(0, (1, 1)), (1, (3, 1)), (2, (3, 2))],
+            &[(0, (2, 1)), (1, (2, 1)), (2, (3, 1))],
+        );
+
+        assert_eq!(moves, vec![(0, Point::new(2, 1))]);
+    }
+
+    // The movement system alone, in its stage; the resources are the ones it requires.
+    //
+    fn movement_app(map: Map) -> App {
+        use GameStage::*;
+
+        let mut app = App::new();
+        app.add_event::<WantsToMove>();
+        app.add_stage_after(CoreStage::Update, PlayerCombat, SystemStage::parallel())
+            .add_stage_after(PlayerCombat, MovePlayer, SystemStage::parallel())
+            .add_stage_after(MovePlayer, PlayerFov, SystemStage::parallel());
+        app.add_system_to_stage(MovePlayer, movement);
+        app.insert_resource(map);
+        app.insert_resource(Camera::new(Point::zero()));
+        app.insert_resource(GameLog::default());
+        app.insert_resource(RunStats::default());
+        app.insert_resource(LightMap::default());
+        app
+    }
+
+    fn spawn_creature(app: &mut App, pos: Point, is_player: bool) -> Entity {
+        let mut entity = app.world.spawn();
+        entity.insert_bundle((
+            PointC(pos),
+            FieldOfView::new(8),
+            Health {
+                current: 10,
+                max: 10,
+            },
+        ));
+        if is_player {
+            entity.insert(Player { map_level: 0 });
         }
+        entity.id()
+    }
+
+    fn send_moves(app: &mut App, moves: &[(Entity, Point)]) {
+        let mut move_events = app.world.get_resource_mut::<Events<WantsToMove>>().unwrap();
+        for (entity, destination) in moves {
+            move_events.send(WantsToMove {
+                entity: *entity,
+                destination: *destination,
+            });
+        }
+    }
+
+    fn position(app: &App, entity: Entity) -> Point {
+        app.world.get::<PointC>(entity).unwrap().0
+    }
+
+    #[test]
+    fn movement_system_applies_the_moves() {
+        let mut map = Map::new();
+        map.tiles[map_idx(21, 20)] = TileType::Wall;
+        let mut app = movement_app(map);
+
+        let player = spawn_creature(&mut app, Point::new(10, 10), true);
+        let walker = spawn_creature(&mut app, Point::new(30, 30), false);
+        let blocked = spawn_creature(&mut app, Point::new(20, 20), false);
+        let stationary = spawn_creature(&mut app, Point::new(40, 40), false);
+        let follower = spawn_creature(&mut app, Point::new(39, 40), false);
+
+        send_moves(
+            &mut app,
+            &[
+                (player, Point::new(11, 10)),
+                (walker, Point::new(31, 30)),
+                (blocked, Point::new(21, 20)),
+                (follower, Point::new(40, 40)),
+            ],
+        );
+        app.update();
+
+        assert_eq!(position(&app, player), Point::new(11, 10));
+        assert_eq!(position(&app, walker), Point::new(31, 30));
+        assert_eq!(position(&app, blocked), Point::new(20, 20));
+        assert_eq!(position(&app, stationary), Point::new(40, 40));
+        assert_eq!(position(&app, follower), Point::new(39, 40));
+    }
+
+    #[test]
+    fn movement_system_resolves_swaps_and_contested_tiles() {
+        let mut app = movement_app(Map::new());
+
+        let player = spawn_creature(&mut app, Point::new(10, 10), true);
+        let first = spawn_creature(&mut app, Point::new(11, 10), false);
+        let second = spawn_creature(&mut app, Point::new(20, 20), false);
+        let third = spawn_creature(&mut app, Point::new(22, 20), false);
+
+        send_moves(
+            &mut app,
+            &[
+                (player, Point::new(11, 10)),
+                (first, Point::new(10, 10)),
+                (third, Point::new(21, 20)),
+                (second, Point::new(21, 20)),
+            ],
+        );
+        app.update();
+
+        assert_eq!(position(&app, player), Point::new(11, 10));
+        assert_eq!(position(&app, first), Point::new(10, 10));
+        assert_eq!(position(&app, second), Point::new(21, 20));
+        assert_eq!(position(&app, third), Point::new(22, 20));
     }
 }
@@ -0,0 +1,61 @@
+use super::ai::*;
+use crate::prelude::*;
+
+// Distance within which a pack member alerts the others.
+const PACK_RADIUS: f32 = 12.0;
+
+pub fn pack(
+    mut move_events: EventWriter<WantsToMove>,
+    mut attack_events: EventWriter<WantsToAttack>,
+    members: Query<
+        (
+            Entity,
+            &PointC,
+            &FieldOfView,
+            &Health,
+            Option<&FleesWhenHurt>,
+            &StatusEffects,
+        ),
+        (With<PackMember>, With<Acting>, Without<Asleep>),
+    >,
+    all_members: Query<(&PointC, &FieldOfView), (With<PackMember>, Without<Asleep>)>,
+    positions: Query<(Entity, &PointC), With<Health>>,
+    player_query: Query<(Entity, &PointC), With<Player>>,
+    map: Res<Map>,
+) {
+    let (player_entity, player_pos) = player_query.single();
+
+    // Members that are not acting in this turn can still alert the others.
+    let alerting_members = all_members
+        .iter()
+        .filter(|(_, fov)| fov.visible_tiles.contains(&player_pos.0))
+        .map(|(pos, _)| pos.0)
+        .collect::<Vec<_>>();
+
+    if alerting_members.is_empty() {
+        return;
+    }
+
+    let dijkstra_map = dijkstra_map_to(player_pos.0, &map);
+
+    for (entity, pos, _, health, flees_when_hurt, status_effects) in members.iter() {
+        let alerted = alerting_members.iter().any(|alerting_pos| {
+            DistanceAlg::Pythagoras.distance2d(*alerting_pos, pos.0) <= PACK_RADIUS
+        });
+
+        if !alerted || is_overridden(status_effects, flees_when_hurt, health) {
+            continue;
+        }
+
+        if let Some(destination) = step_towards(pos.0, player_pos.0, &dijkstra_map, &map) {
+            move_or_attack(
+                entity,
+                destination,
+                player_entity,
+                &positions,
+                &mut move_events,
+                &mut attack_events,
+            );
+        }
+    }
+}
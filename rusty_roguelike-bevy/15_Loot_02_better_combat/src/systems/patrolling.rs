@@ -0,0 +1,113 @@
+use super::ai::*;
+use crate::prelude::*;
+
+pub fn patrolling(
+    mut move_events: EventWriter<WantsToMove>,
+    mut attack_events: EventWriter<WantsToAttack>,
+    mut movers: Query<
+        (
+            Entity,
+            &PointC,
+            &FieldOfView,
+            &Health,
+            &mut Patrolling,
+            Option<&FleesWhenHurt>,
+            &StatusEffects,
+        ),
+        (With<Acting>, Without<Asleep>, Without<MovingRandomly>),
+    >,
+    positions: Query<(Entity, &PointC), With<Health>>,
+    player_query: Query<(Entity, &PointC), With<Player>>,
+    map: Res<Map>,
+    mut rng: ResMut<RandomNumberGenerator>,
+) {
+    let (player_entity, player_pos) = player_query.single();
+    // Computed only if a patroller sees the player.
+    let mut player_dijkstra_map = None;
+
+    for (entity, pos, fov, health, mut patrolling, flees_when_hurt, status_effects) in
+        movers.iter_mut()
+    {
+        if is_overridden(status_effects, flees_when_hurt, health) {
+            continue;
+        }
+
+        // Once the player is spotted, the patrol is abandoned, and a new one starts when the player
+        // is lost.
+        //
+        let destination = if fov.visible_tiles.contains(&player_pos.0) {
+            patrolling.path.clear();
+            let player_dijkstra_map =
+                player_dijkstra_map.get_or_insert_with(|| dijkstra_map_to(player_pos.0, &map));
+            step_towards(pos.0, player_pos.0, player_dijkstra_map, &map)
+        } else {
+            next_patrol_step(pos.0, &mut patrolling, &map, &mut rng)
+        };
+
+        if let Some(destination) = destination {
+            move_or_attack(
+                entity,
+                destination,
+                player_entity,
+                &positions,
+                &mut move_events,
+                &mut attack_events,
+            );
+        }
+    }
+}
+
+// The path is followed until it's completed or interrupted (e.g. when the patroller is pushed away, or
+// the player closes a door), then a new one is computed.
+//
+fn next_patrol_step(
+    pos: Point,
+    patrolling: &mut Patrolling,
+    map: &Map,
+    rng: &mut RandomNumberGenerator,
+) -> Option<Point> {
+    while patrolling.path.last() == Some(&pos) {
+        patrolling.path.pop();
+    }
+
+    let step_valid = patrolling.path.last().is_some_and(|step| {
+        DistanceAlg::Pythagoras.distance2d(pos, *step) <= MELEE_RANGE
+            && map.pathing_cost(map.point2d_to_index(*step)).is_some()
+    });
+
+    if !step_valid {
+        patrolling.path = new_patrol_path(pos, map, rng);
+    }
+
+    patrolling.path.last().copied()
+}
+
+// Returns the steps to a random waypoint, the next one last; unreachable waypoints yield an empty path,
+// so that another one is picked on the next turn.
+//
+fn new_patrol_path(pos: Point, map: &Map, rng: &mut RandomNumberGenerator) -> Vec<Point> {
+    let start = map.point2d_to_index(pos);
+    let waypoints = map
+        .patrol_points
+        .iter()
+        .filter(|idx| **idx != start)
+        .collect::<Vec<_>>();
+
+    if waypoints.is_empty() {
+        return Vec::new();
+    }
+
+    let waypoint = *waypoints[rng.range(0, waypoints.len())];
+    let path = a_star_search(start, waypoint, map);
+
+    if !path.success {
+        return Vec::new();
+    }
+
+    path.steps
+        .iter()
+        .skip(1)
+        .rev()
+        .map(|idx| map.index_to_point2d(*idx))
+        .collect()
+}
@@ -1,73 +1,234 @@
+use crate::components::Name;
 use crate::prelude::*;
+use bracket_lib::prelude::Rect;
+
+const REST_HEALING: i32 = 1;
+const SEARCH_RADIUS: i32 = 2;
+// Percentage; searching repeatedly eventually finds all the traps in range.
+const SEARCH_CHANCE: i32 = 50;
 
 pub fn player_input(
     mut commands: Commands,
     mut move_events: EventWriter<WantsToMove>,
     mut attack_events: EventWriter<WantsToAttack>,
-    active_item_events: EventWriter<ActivateItem>,
-    player_query: Query<(Entity, &PointC), With<Player>>,
-    level_items_query: Query<(Entity, &PointC), With<Item>>,
-    carried_items_query: Query<(Entity, &Carried), With<Item>>,
-    weapons_query: Query<&Weapon>,
-    carried_weapons_query: Query<(Entity, &Carried), With<Weapon>>,
+    mut active_item_events: EventWriter<ActivateItem>,
+    mut player_query: Query<
+        (Entity, &PointC, &StatusEffects, &FieldOfView, &mut Health),
+        With<Player>,
+    >,
+    level_items_query: Query<(Entity, &PointC, &Name), With<Item>>,
+    carried_items_query: CarriedItemsQuery,
+    equippables_query: Query<&Equippable>,
+    equipped_query: Query<(&Carried, &Equippable, Option<&Ranged>), With<Equipped>>,
+    throwables_query: Query<(&Ranged, Option<&AreaOfEffect>), Without<Equippable>>,
     enemies_query: Query<(Entity, &PointC), With<Enemy>>,
     key: Option<Res<VirtualKeyCode>>,
+    (mut map, key_bindings, inventory_screen, light_map, identification): (
+        ResMut<Map>,
+        Res<KeyBindings>,
+        Res<InventoryScreen>,
+        Res<LightMap>,
+        Res<Identification>,
+    ),
+    mut rng: ResMut<RandomNumberGenerator>,
+    mut game_log: ResMut<GameLog>,
 ) {
-    let (player_entity, player_pos) = player_query.single();
+    let (player_entity, player_pos, status_effects, player_fov, mut player_health) =
+        player_query.single_mut();
 
     if let Some(key) = key.as_deref() {
-        let delta = match key {
-            VirtualKeyCode::Left => Point::new(-1, 0),
-            VirtualKeyCode::Right => Point::new(1, 0),
-            VirtualKeyCode::Up => Point::new(0, -1),
-            VirtualKeyCode::Down => Point::new(0, 1),
-            VirtualKeyCode::G => {
-                for (entity, item_pos) in level_items_query.iter() {
+        // Any keypress interrupts the automatic movement.
+        commands.remove_resource::<AutoMove>();
+
+        // Unbound keys are ignored.
+        let action = match key_bindings.action(*key) {
+            Some(action) => action,
+            None => {
+                commands.remove_resource::<VirtualKeyCode>();
+                return;
+            }
+        };
+
+        // Saving and changing level require exclusive access to the world, so they're performed by the
+        // main loop. Viewing the log or the inventory doesn't take a turn.
+        let player_tile = map.tiles[map.point2d_to_index(player_pos.0)];
+        let main_loop_state = match action {
+            Action::SaveAndQuit => Some(TurnState::SaveAndQuit),
+            Action::MessageLog => Some(TurnState::ShowingLog),
+            Action::Inventory => Some(TurnState::ShowingInventory),
+            Action::Descend if player_tile == TileType::Exit => Some(TurnState::NextLevel),
+            Action::Ascend if player_tile == TileType::StairsUp => Some(TurnState::PreviousLevel),
+            _ => None,
+        };
+        if action == Action::Explore {
+            commands.insert_resource(AutoMove {
+                destination: None,
+                known_enemies: enemies_query
+                    .iter()
+                    .filter(|(_, pos)| light_map.can_see(player_pos.0, player_fov, pos.0))
+                    .map(|(entity, _)| entity)
+                    .collect(),
+            });
+            commands.remove_resource::<VirtualKeyCode>();
+            return;
+        }
+
+        // Closing a door takes a turn only if there's one to close; occupied doorways can't be closed.
+        //
+        if action == Action::CloseDoor {
+            let door = DIRECTIONS
+                .iter()
+                .map(|delta| player_pos.0 + *delta)
+                .find(|pt| {
+                    map.try_idx(*pt)
+                        .is_some_and(|idx| map.tiles[idx] == TileType::OpenDoor)
+                        && !enemies_query.iter().any(|(_, pos)| pos.0 == *pt)
+                        && !level_items_query.iter().any(|(_, pos, _)| pos.0 == *pt)
+                });
+
+            if let Some(door) = door {
+                let idx = map.point2d_to_index(door);
+                map.tiles[idx] = TileType::Door;
+                commands
+                    .entity(player_entity)
+                    .insert(player_fov.clone_dirty());
+                game_log.add("You close the door.", GRAY);
+                commands.insert_resource(TurnState::PlayerTurn);
+            } else {
+                game_log.add("There's no door to close.", GRAY);
+            }
+            commands.remove_resource::<VirtualKeyCode>();
+            return;
+        }
+
+        if let Some(main_loop_state) = main_loop_state {
+            commands.insert_resource(main_loop_state);
+            commands.remove_resource::<VirtualKeyCode>();
+            return;
+        }
+
+        let carried_item = match action {
+            Action::UseItem(n) => carried_items(
+                player_entity,
+                &carried_items_query,
+                &identification,
+                inventory_screen.sort,
+            )
+            .get(n)
+            .map(|entry| entry.item()),
+            _ => None,
+        };
+
+        // Ranged items and weapons need a target, which is selected before the turn is taken.
+        //
+        let targeting = match action {
+            Action::Fire => equipped_query
+                .iter()
+                .find_map(|(carried, _, ranged)| {
+                    (carried.0 == player_entity).then_some(ranged).flatten()
+                })
+                .map(|ranged| {
+                    Targeting::new(TargetingSource::Weapon, ranged.range, 0, player_pos.0)
+                }),
+            _ => carried_item.and_then(|item| {
+                throwables_query
+                    .get(item)
+                    .ok()
+                    .map(|(ranged, area_of_effect)| {
+                        Targeting::for_item(item, ranged, area_of_effect, player_pos.0)
+                    })
+            }),
+        };
+        if let Some(targeting) = targeting {
+            commands.insert_resource(targeting);
+            commands.insert_resource(TurnState::SelectingTarget);
+            commands.remove_resource::<VirtualKeyCode>();
+            return;
+        }
+
+        if let Some(item) = carried_item {
+            active_item_events.send(ActivateItem {
+                used_by: player_entity,
+                item,
+                target: None,
+            });
+        }
+
+        let delta = match action {
+            Action::Move(dx, dy) => Point::new(dx, dy),
+            Action::PickUp => {
+                for (entity, item_pos, name) in level_items_query.iter() {
                     if item_pos.0 == player_pos.0 {
                         commands.entity(entity).remove::<PointC>();
                         commands.entity(entity).insert(Carried(player_entity));
+                        game_log.add(
+                            format!("You pick up the {}.", identification.display_name(&name.0)),
+                            CYAN,
+                        );
 
-                        if weapons_query.get(entity).is_ok() {
-                            for (e, c) in carried_weapons_query.iter() {
-                                if c.0 == player_entity {
-                                    commands.entity(e).despawn();
-                                }
+                        // Picked up equipment is worn only if its slot is free; otherwise, the player
+                        // can swap it by using it.
+                        if let Ok(equippable) = equippables_query.get(entity) {
+                            let slot_taken = equipped_query.iter().any(|(carried, other, _)| {
+                                carried.0 == player_entity && other.slot == equippable.slot
+                            });
+                            if !slot_taken {
+                                commands.entity(entity).insert(Equipped);
                             }
                         }
                     }
                 }
                 Point::new(0, 0)
             }
-            VirtualKeyCode::Key1 => {
-                use_item(0, player_entity, active_item_events, carried_items_query)
-            }
-            VirtualKeyCode::Key2 => {
-                use_item(1, player_entity, active_item_events, carried_items_query)
-            }
-            VirtualKeyCode::Key3 => {
-                use_item(2, player_entity, active_item_events, carried_items_query)
-            }
-            VirtualKeyCode::Key4 => {
-                use_item(3, player_entity, active_item_events, carried_items_query)
-            }
-            VirtualKeyCode::Key5 => {
-                use_item(4, player_entity, active_item_events, carried_items_query)
-            }
-            VirtualKeyCode::Key6 => {
-                use_item(5, player_entity, active_item_events, carried_items_query)
-            }
-            VirtualKeyCode::Key7 => {
-                use_item(6, player_entity, active_item_events, carried_items_query)
-            }
-            VirtualKeyCode::Key8 => {
-                use_item(7, player_entity, active_item_events, carried_items_query)
+            // Resting is possible only when no enemy is in sight.
+            Action::Wait => {
+                let enemy_in_sight = enemies_query
+                    .iter()
+                    .any(|(_, pos)| light_map.can_see(player_pos.0, player_fov, pos.0));
+                if !enemy_in_sight {
+                    player_health.current =
+                        i32::min(player_health.max, player_health.current + REST_HEALING);
+                }
+                Point::new(0, 0)
             }
-            VirtualKeyCode::Key9 => {
-                use_item(8, player_entity, active_item_events, carried_items_query)
+            Action::Search => {
+                let mut traps_found = 0;
+                Rect::with_exact(
+                    player_pos.0.x - SEARCH_RADIUS,
+                    player_pos.0.y - SEARCH_RADIUS,
+                    // The rectangle excludes its right and bottom edges.
+                    player_pos.0.x + SEARCH_RADIUS + 1,
+                    player_pos.0.y + SEARCH_RADIUS + 1,
+                )
+                .for_each(|pt| {
+                    if let Some(idx) = map.try_idx(pt) {
+                        if map.tiles[idx] == TileType::HiddenTrap
+                            && rng.range(0, 100) < SEARCH_CHANCE
+                        {
+                            map.tiles[idx] = TileType::Trap;
+                            traps_found += 1;
+                        }
+                    }
+                });
+
+                if traps_found > 0 {
+                    game_log.add("You find a hidden trap!", ORANGE);
+                } else {
+                    game_log.add("You search the area, but find nothing.", GRAY);
+                }
+                Point::new(0, 0)
             }
             _ => Point::new(0, 0),
         };
 
+        // A confused player stumbles in a random direction.
+        let delta =
+            if (delta.x != 0 || delta.y != 0) && status_effects.has(StatusEffectKind::Confusion) {
+                DIRECTIONS[rng.range(0, DIRECTIONS.len())]
+            } else {
+                delta
+            };
+
         let destination = player_pos.0 + delta;
 
         if delta.x != 0 || delta.y != 0 {
@@ -81,6 +242,7 @@ pub fn player_input(
                     attack_events.send(WantsToAttack {
                         attacker: player_entity,
                         victim: entity,
+                        ranged: false,
                     });
                 }
             }
@@ -105,26 +267,3 @@ pub fn player_input(
         commands.remove_resource::<VirtualKeyCode>();
     }
 }
-
-fn use_item(
-    n: usize,
-    player_entity: Entity,
-    mut active_item_events: EventWriter<ActivateItem>,
-    items: Query<(Entity, &Carried), With<Item>>,
-) -> Point {
-    let item_entity = items
-        .iter()
-        .filter(|(_, carried)| carried.0 == player_entity)
-        .enumerate()
-        .filter(|(item_count, (_, _))| *item_count == n)
-        .find_map(|(_, (item_entity, _))| Some(item_entity));
-
-    if let Some(item_entity) = item_entity {
-        active_item_events.send(ActivateItem {
-            used_by: player_entity,
-            item: item_entity,
-        });
-    }
-
-    Point::zero()
-}
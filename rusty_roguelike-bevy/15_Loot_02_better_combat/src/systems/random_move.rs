@@ -3,27 +3,42 @@ use crate::prelude::*;
 pub fn random_move(
     mut move_events: EventWriter<WantsToMove>,
     mut attack_events: EventWriter<WantsToAttack>,
-    movers: Query<(Entity, &PointC), With<MovingRandomly>>,
+    movers: Query<
+        (
+            Entity,
+            &PointC,
+            &Health,
+            Option<&MovingRandomly>,
+            Option<&FleesWhenHurt>,
+            Option<&StatusEffects>,
+        ),
+        (With<Enemy>, With<Acting>, Without<Asleep>),
+    >,
     positions: Query<(Entity, &PointC), With<Health>>,
     player_query: Query<Entity, With<Player>>,
+    mut rng: ResMut<RandomNumberGenerator>,
 ) {
-    movers.iter().for_each(|(entity, pos)| {
-        let mut rng = RandomNumberGenerator::new();
-        let destination = match rng.range(0, 4) {
-            0 => Point::new(-1, 0),
-            1 => Point::new(1, 0),
-            2 => Point::new(0, -1),
-            _ => Point::new(0, 1),
-        } + pos.0;
+    // Confused monsters move randomly, regardless of their usual behavior.
+    let movers = movers
+        .iter()
+        .filter(
+            |(_, _, health, moving_randomly, flees_when_hurt, status_effects)| {
+                let fleeing = flees_when_hurt.is_some_and(|flees| flees.is_fleeing(health));
+                status_effects.is_some_and(|s| s.has(StatusEffectKind::Confusion))
+                    || (moving_randomly.is_some() && !fleeing)
+            },
+        )
+        .map(|(entity, pos, _, _, _, _)| (entity, pos));
+
+    movers.for_each(|(entity, pos)| {
+        let destination = DIRECTIONS[rng.range(0, DIRECTIONS.len())] + pos.0;
 
         // This variable name is a bit misleading, as enemies don't attack each other.
         let mut attacked = false;
 
-        // Something important to be aware of is that this logic doesn't prevent multiple enemies to
-        // move to the same position if it's empty, as they move only in a subsequent stage. Solving
-        // this issue needs to consider edge cases, like an enemy in a corridor surrounded by two enemies
-        // intending to move around it. This can be intended or not; some users discussed it - see source
-        // project [issue tracker](https://github.com/thebracket/HandsOnRust/pull/1)).
+        // This logic doesn't prevent multiple enemies to move to the same position if it's empty, as they
+        // move only in a subsequent stage; differently from the source project, conflicts are resolved
+        // by the movement system (see source project [issue tracker](https://github.com/thebracket/HandsOnRust/pull/1)).
         //
         for (victim, target_pos) in positions.iter() {
             if target_pos.0 == destination {
@@ -31,6 +46,7 @@ pub fn random_move(
                     attack_events.send(WantsToAttack {
                         attacker: entity,
                         victim: victim,
+                        ranged: false,
                     });
                 }
                 attacked = true;
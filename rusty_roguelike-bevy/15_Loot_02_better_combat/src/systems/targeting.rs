@@ -0,0 +1,89 @@
+use crate::prelude::*;
+
+pub fn targeting(
+    mut commands: Commands,
+    mut attack_events: EventWriter<WantsToAttack>,
+    mut active_item_events: EventWriter<ActivateItem>,
+    player_query: Query<(Entity, &PointC, &FieldOfView), With<Player>>,
+    enemies_query: Query<(Entity, &PointC), With<Enemy>>,
+    mut targeting: ResMut<Targeting>,
+    key: Option<Res<VirtualKeyCode>>,
+    (mouse_pos, mouse_click, camera, map): (Res<Point>, Res<MouseClick>, Res<Camera>, Res<Map>),
+) {
+    let (player_entity, player_pos, player_fov) = player_query.single();
+
+    let mouse_map_pos = *mouse_pos + Point::new(camera.left_x, camera.top_y);
+    match targeting.last_mouse_pos {
+        Some(last_mouse_pos) if last_mouse_pos != *mouse_pos => targeting.cursor = mouse_map_pos,
+        _ => {}
+    }
+    targeting.last_mouse_pos = Some(*mouse_pos);
+
+    let mut confirmed = mouse_click.0;
+
+    if let Some(key) = key.as_deref() {
+        match key {
+            VirtualKeyCode::Left => targeting.cursor.x -= 1,
+            VirtualKeyCode::Right => targeting.cursor.x += 1,
+            VirtualKeyCode::Up => targeting.cursor.y -= 1,
+            VirtualKeyCode::Down => targeting.cursor.y += 1,
+            // Cycles through the visible enemies, sorted by distance.
+            VirtualKeyCode::Tab => {
+                let mut targets = enemies_query
+                    .iter()
+                    .map(|(_, pos)| pos.0)
+                    .filter(|pos| {
+                        targeting.is_valid_target(*pos, player_pos.0, player_fov, map.as_ref())
+                    })
+                    .collect::<Vec<_>>();
+                targets.sort_by(|a, b| {
+                    let distance_a = DistanceAlg::Pythagoras.distance2d(player_pos.0, *a);
+                    let distance_b = DistanceAlg::Pythagoras.distance2d(player_pos.0, *b);
+                    distance_a.partial_cmp(&distance_b).unwrap()
+                });
+                let next_index = targets
+                    .iter()
+                    .position(|pos| *pos == targeting.cursor)
+                    .map_or(0, |i| i + 1);
+                if let Some(target) = targets.get(next_index).or_else(|| targets.first()) {
+                    targeting.cursor = *target;
+                }
+            }
+            VirtualKeyCode::Return | VirtualKeyCode::F => confirmed = true,
+            VirtualKeyCode::Escape => {
+                commands.remove_resource::<Targeting>();
+                commands.insert_resource(TurnState::AwaitingInput);
+            }
+            _ => {}
+        }
+
+        // See player_input.
+        commands.remove_resource::<VirtualKeyCode>();
+    }
+
+    let target = targeting.cursor;
+
+    if confirmed && targeting.is_valid_target(target, player_pos.0, player_fov, map.as_ref()) {
+        match targeting.source {
+            TargetingSource::Item(item) => active_item_events.send(ActivateItem {
+                used_by: player_entity,
+                item,
+                target: Some(target),
+            }),
+            TargetingSource::Weapon => {
+                for (victim, pos) in enemies_query.iter() {
+                    if pos.0 == target {
+                        attack_events.send(WantsToAttack {
+                            attacker: player_entity,
+                            victim,
+                            ranged: true,
+                        });
+                    }
+                }
+            }
+        }
+
+        commands.remove_resource::<Targeting>();
+        commands.insert_resource(TurnState::PlayerTurn);
+    }
+}
@@ -0,0 +1,45 @@
+use crate::prelude::*;
+
+pub fn targeting_render(
+    player_query: Query<(&PointC, &FieldOfView), With<Player>>,
+    targeting: Option<Res<Targeting>>,
+    (map, camera): (Res<Map>, Res<Camera>),
+) {
+    let targeting = if let Some(targeting) = targeting {
+        targeting
+    } else {
+        return;
+    };
+
+    let mut draw_batch = DrawBatch::new();
+    draw_batch.target(0);
+    let offset = Point::new(camera.left_x, camera.top_y);
+
+    let (player_pos, player_fov) = player_query.single();
+
+    // The tiles are tinted on the map layer, so that the entities are still visible.
+    for pt in player_fov.visible_tiles.iter() {
+        if targeting.is_valid_target(*pt, player_pos.0, player_fov, map.as_ref()) {
+            draw_batch.set_bg(*pt - offset, DARK_BLUE);
+        }
+    }
+
+    let cursor_color =
+        if targeting.is_valid_target(targeting.cursor, player_pos.0, player_fov, map.as_ref()) {
+            GREEN
+        } else {
+            RED
+        };
+
+    if targeting.radius > 0 {
+        for pt in player_fov.visible_tiles.iter() {
+            if DistanceAlg::Pythagoras.distance2d(targeting.cursor, *pt) <= targeting.radius as f32
+            {
+                draw_batch.set_bg(*pt - offset, DARK_RED);
+            }
+        }
+    }
+    draw_batch.set_bg(targeting.cursor - offset, cursor_color);
+
+    draw_batch.submit(100).expect("Batch error");
+}
@@ -3,21 +3,27 @@ use crate::prelude::*;
 
 pub fn tooltips(
     positions: Query<(&PointC, &components::Name, Option<&Health>)>,
-    player_fov_query: Query<&FieldOfView, With<Player>>,
-    (mouse_pos, camera): (Res<Point>, Res<Camera>),
+    player_query: Query<(&PointC, &FieldOfView), With<Player>>,
+    (mouse_pos, camera, light_map, identification): (
+        Res<Point>,
+        Res<Camera>,
+        Res<LightMap>,
+        Res<Identification>,
+    ),
 ) {
     let offset = Point::new(camera.left_x, camera.top_y);
     let map_pos = *mouse_pos + offset;
     let mut draw_batch = DrawBatch::new();
     draw_batch.target(2);
-    let player_fov = player_fov_query.single();
+    let (player_pos, player_fov) = player_query.single();
     for (pos, name, health) in positions.iter() {
-        if pos.0 == map_pos && player_fov.visible_tiles.contains(&pos.0) {
+        if pos.0 == map_pos && light_map.can_see(player_pos.0, player_fov, pos.0) {
             let screen_pos = *mouse_pos * 4;
+            let name = identification.display_name(&name.0);
             let display = if let Some(health) = health {
-                format!("{} : {} hp", &name.0, health.current)
+                format!("{} : {} hp", name, health.current)
             } else {
-                name.0.clone()
+                name
             };
             draw_batch.print(screen_pos, &display);
         }
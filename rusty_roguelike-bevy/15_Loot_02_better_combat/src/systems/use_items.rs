@@ -1,21 +1,198 @@
+use super::combat::kill;
+use crate::components::Name;
 use crate::prelude::*;
+use bracket_lib::prelude::Rect;
 
 pub fn use_items(
     mut commands: Commands,
     mut activate_item_events: EventReader<ActivateItem>,
-    items_query: Query<(Option<&ProvidesHealing>, Option<&ProvidesDungeonMap>)>,
+    items_query: Query<(
+        Option<&ProvidesHealing>,
+        Option<&ProvidesDungeonMap>,
+        Option<&ProvidesFood>,
+        Option<&ProvidesStatusEffects>,
+        Option<&Damage>,
+        Option<&AreaOfEffect>,
+        Option<&Equippable>,
+        Option<&Equipped>,
+        Option<&Key>,
+        Option<&ProvidesIdentification>,
+    )>,
+    equipped_query: Query<(Entity, &Carried, &Equippable), With<Equipped>>,
+    creatures_query: Query<(Entity, &PointC), With<Health>>,
+    player_query: Query<&Player>,
+    mut experience_query: Query<&mut Experience>,
+    experience_value_query: Query<&ExperienceValue>,
     mut health_query: Query<&mut Health>,
-    mut map: ResMut<Map>,
+    mut status_effects_query: Query<&mut StatusEffects>,
+    mut hunger_query: Query<&mut Hunger>,
+    names_query: Query<&Name>,
+    carried_query: Query<(&Carried, &Name)>,
+    fov_query: Query<(Entity, &FieldOfView)>,
+    (mut map, mut identification, mut run_stats, mut game_log): (
+        ResMut<Map>,
+        ResMut<Identification>,
+        ResMut<RunStats>,
+        ResMut<GameLog>,
+    ),
 ) {
     let mut healing_to_apply = Vec::<(Entity, i32)>::new();
+    // (victim, damage, attacker)
+    let mut damage_to_apply = Vec::<(Entity, i32, Entity)>::new();
     for activate in activate_item_events.iter() {
-        if let Ok((healing, mapper)) = items_query.get(activate.item) {
-            if let Some(healing) = healing {
-                healing_to_apply.push((activate.used_by, healing.amount));
+        if let Ok((
+            healing,
+            mapper,
+            food,
+            status_provider,
+            damage,
+            area_of_effect,
+            equippable,
+            equipped,
+            key,
+            identifier,
+        )) = items_query.get(activate.item)
+        {
+            let item_name = entity_name(activate.item, &names_query);
+            let display_name = identification.display_name(&item_name);
+
+            // Equipment is not consumed; activating it toggles it, replacing the item in the same
+            // slot, if any.
+            //
+            if let Some(equippable) = equippable {
+                if equipped.is_some() {
+                    commands.entity(activate.item).remove::<Equipped>();
+                    game_log.add(format!("You unequip the {}.", display_name), CYAN);
+                } else {
+                    for (other_item, carried, other_equippable) in equipped_query.iter() {
+                        if carried.0 == activate.used_by && other_equippable.slot == equippable.slot
+                        {
+                            commands.entity(other_item).remove::<Equipped>();
+                        }
+                    }
+                    commands.entity(activate.item).insert(Equipped);
+                    game_log.add(format!("You equip the {}.", display_name), CYAN);
+                }
+                continue;
+            }
+
+            // Keys are used by walking into a locked door.
+            if key.is_some() {
+                game_log.add("You need to walk into a locked door to use a key.", GRAY);
+                continue;
+            }
+
+            game_log.add(format!("You use the {}.", display_name), CYAN);
+
+            // Using an item reveals what it is.
+            if player_query.get(activate.used_by).is_ok() {
+                run_stats.add_item_used(&item_name);
+
+                if identification.identify(&item_name) {
+                    game_log.add(format!("It was a {}.", item_name), YELLOW);
+                }
+            }
+
+            // Targeted items affect the creatures in the target area, rather than the user.
+            //
+            let recipients = if let Some(target) = activate.target {
+                let radius = area_of_effect.map_or(0, |aoe| aoe.radius) as f32;
+                creatures_query
+                    .iter()
+                    .filter(|(_, pos)| DistanceAlg::Pythagoras.distance2d(target, pos.0) <= radius)
+                    .map(|(entity, _)| entity)
+                    .collect::<Vec<_>>()
+            } else {
+                vec![activate.used_by]
+            };
+
+            // Explosions break through the cracked walls.
+            //
+            if let (Some(target), Some(area_of_effect), Some(_)) =
+                (activate.target, area_of_effect, damage)
+            {
+                let mut walls_destroyed = false;
+                Rect::with_exact(
+                    target.x - area_of_effect.radius,
+                    target.y - area_of_effect.radius,
+                    // The rectangle excludes its right and bottom edges.
+                    target.x + area_of_effect.radius + 1,
+                    target.y + area_of_effect.radius + 1,
+                )
+                .for_each(|pt| {
+                    if let Some(idx) = map.try_idx(pt) {
+                        if map.tiles[idx] == TileType::CrackedWall
+                            && DistanceAlg::Pythagoras.distance2d(target, pt)
+                                <= area_of_effect.radius as f32
+                        {
+                            map.tiles[idx] = TileType::Floor;
+                            walls_destroyed = true;
+                        }
+                    }
+                });
+
+                if walls_destroyed {
+                    game_log.add("The cracked walls crumble.", YELLOW);
+                    fov_query.for_each(|(entity, fov)| {
+                        commands.entity(entity).insert(fov.clone_dirty());
+                    });
+                }
+            }
+
+            for recipient in recipients.iter() {
+                if let Some(healing) = healing {
+                    healing_to_apply.push((*recipient, healing.amount));
+                }
+                if let Some(damage) = damage {
+                    damage_to_apply.push((*recipient, damage.0, activate.used_by));
+                }
+            }
+
+            // Food always feeds the user.
+            if let (Some(food), Ok(mut hunger)) = (food, hunger_query.get_mut(activate.used_by)) {
+                hunger.food = i32::min(MAX_FOOD, hunger.food + food.amount);
+                game_log.add("You feel satiated.", GREEN);
+            }
+
+            if identifier.is_some() {
+                let mut anything_identified = false;
+                for (carried, name) in carried_query.iter() {
+                    if carried.0 == activate.used_by && !identification.is_identified(&name.0) {
+                        let appearance = identification.display_name(&name.0);
+                        identification.identify(&name.0);
+                        game_log.add(format!("The {} is a {}.", appearance, name.0), YELLOW);
+                        anything_identified = true;
+                    }
+                }
+                if !anything_identified {
+                    game_log.add("You learn nothing new.", GRAY);
+                }
             }
 
             if mapper.is_some() {
                 map.revealed_tiles.iter_mut().for_each(|t| *t = true);
+                game_log.add("The layout of the dungeon is revealed.", CYAN);
+            }
+
+            if let Some(status_provider) = status_provider {
+                for recipient in recipients.iter() {
+                    if let Ok(mut status_effects) = status_effects_query.get_mut(*recipient) {
+                        for effect in status_provider.0.iter() {
+                            status_effects.add(*effect);
+
+                            let effect_name = effect.kind.name().to_lowercase();
+                            if player_query.get(*recipient).is_ok() {
+                                game_log.add(format!("You are {}.", effect_name), MAGENTA);
+                            } else {
+                                let recipient_name = entity_name(*recipient, &names_query);
+                                game_log.add(
+                                    format!("The {} is {}.", recipient_name, effect_name),
+                                    MAGENTA,
+                                );
+                            }
+                        }
+                    }
+                }
             }
         }
         commands.entity(activate.item).despawn();
@@ -23,7 +200,52 @@ pub fn use_items(
 
     for heal in &healing_to_apply {
         if let Ok(mut health) = health_query.get_mut(heal.0) {
+            let previous_health = health.current;
             health.current = i32::min(health.max, health.current + heal.1);
+
+            if player_query.get(heal.0).is_ok() {
+                game_log.add(
+                    format!("You recover {} health.", health.current - previous_health),
+                    GREEN,
+                );
+            }
+        }
+    }
+
+    // The player's death is handled by end_turn.
+    for (victim, damage, attacker) in &damage_to_apply {
+        if let Ok(mut health) = health_query.get_mut(*victim) {
+            let was_alive = health.current > 0;
+            health.current -= damage;
+
+            if player_query.get(*victim).is_ok() {
+                game_log.add(format!("You take {} damage.", damage), RED);
+            } else if was_alive {
+                let victim_name = entity_name(*victim, &names_query);
+                if health.current < 1 {
+                    game_log.add(format!("The {} is destroyed.", victim_name), ORANGE);
+                } else {
+                    game_log.add(
+                        format!("The {} takes {} damage.", victim_name, damage),
+                        YELLOW,
+                    );
+                }
+            }
+
+            if health.current < 1 && player_query.get(*victim).is_err() && was_alive {
+                kill(
+                    &mut commands,
+                    *victim,
+                    *attacker,
+                    &mut experience_query,
+                    &experience_value_query,
+                );
+
+                if player_query.get(*attacker).is_ok() {
+                    let victim_name = entity_name(*victim, &names_query);
+                    run_stats.add_kill(&victim_name);
+                }
+            }
         }
     }
 }
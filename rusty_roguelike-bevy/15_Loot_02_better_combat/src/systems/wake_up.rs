@@ -0,0 +1,34 @@
+use crate::prelude::*;
+
+// Fights are louder than footsteps, so they're heard from farther away.
+const FIGHT_NOISE_MULTIPLIER: i32 = 3;
+
+pub fn wake_up(
+    mut commands: Commands,
+    mut attack_events: EventReader<WantsToAttack>,
+    sleepers: Query<(Entity, &PointC, &Asleep)>,
+    positions: Query<&PointC>,
+    player_query: Query<&PointC, With<Player>>,
+) {
+    let player_pos = player_query.single().0;
+
+    let fight_positions = attack_events
+        .iter()
+        .filter_map(|attack| positions.get(attack.victim).ok())
+        .map(|pos| pos.0)
+        .collect::<Vec<_>>();
+
+    for (entity, pos, asleep) in sleepers.iter() {
+        let hears_footsteps =
+            DistanceAlg::Pythagoras.distance2d(pos.0, player_pos) <= asleep.hearing as f32;
+        let hears_fight = fight_positions.iter().any(|fight_pos| {
+            DistanceAlg::Pythagoras.distance2d(pos.0, *fight_pos)
+                <= (asleep.hearing * FIGHT_NOISE_MULTIPLIER) as f32
+        });
+
+        // The monster starts acting from the next turn.
+        if hears_footsteps || hears_fight {
+            commands.entity(entity).remove::<Asleep>();
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use crate::prelude::*;
+
+pub enum TargetingSource {
+    // A consumable (thrown items, spell scrolls); it's activated on the target.
+    Item(Entity),
+    // The equipped ranged weapon; it attacks the target.
+    Weapon,
+}
+
+// Present while the player selects a target (TurnState::SelectingTarget).
+//
+pub struct Targeting {
+    pub source: TargetingSource,
+    pub range: i32,
+    pub radius: i32,
+    pub cursor: Point,
+    // The cursor follows the mouse only when the latter moves, so that the keyboard can be used as well.
+    pub last_mouse_pos: Option<Point>,
+}
+
+impl Targeting {
+    pub fn new(source: TargetingSource, range: i32, radius: i32, player_pos: Point) -> Self {
+        Self {
+            source,
+            range,
+            radius,
+            cursor: player_pos,
+            last_mouse_pos: None,
+        }
+    }
+
+    // Consumables (thrown items, spell scrolls) may affect an area around the target.
+    //
+    pub fn for_item(
+        item: Entity,
+        ranged: &Ranged,
+        area_of_effect: Option<&AreaOfEffect>,
+        player_pos: Point,
+    ) -> Self {
+        Self::new(
+            TargetingSource::Item(item),
+            ranged.range,
+            area_of_effect.map_or(0, |aoe| aoe.radius),
+            player_pos,
+        )
+    }
+
+    // A target must be visible, in range, and not hidden behind opaque tiles (the player's field of view
+    // is more permissive than a projectile line).
+    //
+    pub fn is_valid_target(
+        &self,
+        target: Point,
+        player_pos: Point,
+        player_fov: &FieldOfView,
+        map: &Map,
+    ) -> bool {
+        player_fov.visible_tiles.contains(&target)
+            && DistanceAlg::Pythagoras.distance2d(player_pos, target) <= self.range as f32
+            && line2d_bresenham(player_pos, target)
+                .iter()
+                .filter(|pt| **pt != player_pos && **pt != target)
+                .all(|pt| !map.is_opaque(map.point2d_to_index(*pt)))
+    }
+}
+
+// Whether the left mouse button has been clicked in the current frame.
+//
+pub struct MouseClick(pub bool);
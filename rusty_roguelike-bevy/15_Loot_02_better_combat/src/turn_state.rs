@@ -1,9 +1,36 @@
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub enum TurnState {
     AwaitingInput,
+    SelectingTarget,
     PlayerTurn,
     MonsterTurn,
     GameOver,
     Victory,
+    LevelUp,
     NextLevel,
+    PreviousLevel,
+    SaveAndQuit,
+    ShowingLog,
+    ShowingInventory,
+}
+
+// Set when the player dies, so that the game over screen and the morgue file can tell how the run ended.
+//
+pub enum DeathCause {
+    // Name of the attacker.
+    Monster(String),
+    Trap,
+    Poison,
+    Starvation,
+}
+
+impl DeathCause {
+    pub fn description(&self) -> String {
+        match self {
+            DeathCause::Monster(name) => format!("Slain by the {}", name),
+            DeathCause::Trap => "Killed by a trap".to_string(),
+            DeathCause::Poison => "Succumbed to poison".to_string(),
+            DeathCause::Starvation => "Starved to death".to_string(),
+        }
+    }
 }